@@ -1,4 +1,96 @@
+// Only the parsing half of the robot's path code is used by the build script,
+// and its `Float` imports have nothing to bring in here
+#![allow(dead_code, unused_imports)]
+
+extern crate alloc;
+
+use std::{fs, path::Path, process};
+
+/// Directory holding every routine embedded in `autonomous::PATHS`
+const PATHS_DIR: &str = "src/autonomous/paths";
+
+/// Directory holding the fragments routines can `include`
+const FRAGMENTS_DIR: &str = "src/autonomous/paths/fragments";
+
+/// Every robot source compiled into the build script below, so changing any of
+/// them checks the routines again
+const SOURCES: [&str; 6] = [
+    "src/autonomous/command.rs",
+    "src/autonomous/error.rs",
+    "src/autonomous/fragments.rs",
+    "src/autonomous/parse.rs",
+    "src/pose.rs",
+    "src/subsystems/command.rs",
+];
+
+// The path language is compiled straight from the robot's sources so the
+// build checks routines with exactly the same parser the robot uses.
+#[path = "src/autonomous"]
+mod autonomous {
+    pub mod command;
+    pub mod error;
+    pub mod fragments;
+    mod parse;
+}
+
+#[path = "src/pose.rs"]
+mod pose;
+
+/// `std` already has float math here, so the robot's `Float` has nothing to add
+mod platform {
+    pub trait Float {}
+}
+
+#[path = "src/subsystems"]
+mod subsystems {
+    pub mod command;
+}
+
+/// Parses every botpath file and fails the build if any of them are invalid
+fn lint_paths() {
+    println!("cargo:rerun-if-changed={PATHS_DIR}");
+    println!("cargo:rerun-if-changed={FRAGMENTS_DIR}");
+    for source in SOURCES {
+        println!("cargo:rerun-if-changed={source}");
+    }
+
+    let mut entries: Vec<_> = fs::read_dir(PATHS_DIR)
+        .expect("paths directory should exist")
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "botpath"))
+        .collect();
+    entries.sort();
+
+    // Report every broken routine at once rather than stopping at the first
+    let mut failed = false;
+    for path in &entries {
+        if let Err(message) = lint_path(path) {
+            println!("cargo:warning={message}");
+            failed = true;
+        }
+    }
+
+    if failed {
+        eprintln!("error: one or more botpath routines failed to parse");
+        process::exit(1);
+    }
+}
+
+/// Parses a single botpath file, returning a `file:line:column` message on failure
+fn lint_path(path: &Path) -> Result<(), String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("{name}: could not be read: {e}"))?;
+
+    autonomous::command::path_to_commands(&contents)
+        .map(|_| ())
+        .map_err(|e| format!("{name}: {e}"))
+}
+
 fn main() {
+    lint_paths();
+
     slint_build::compile_with_config(
         "ui/main.slint",
         slint_build::CompilerConfiguration::new()
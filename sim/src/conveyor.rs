@@ -0,0 +1,191 @@
+//! The intake's conveyor, carrying rings past the optical sensor to the top
+//! where they're either scored or flung off
+
+use crate::{config::Color, hardware::mock::MockOptical};
+
+/// Chain links the conveyor moves for each revolution of the intake motor
+const SPROCKET_TEETH: f64 = 6.0;
+
+/// Chain links from the optical sensor to the top of the conveyor, measured
+/// on the robot rather than taken from the intake's sorting distance, so a
+/// wrong sorting distance shows up as missed sorts
+const SENSOR_TO_TOP: f64 = 14.0;
+
+/// How far past the top a ring can be when the conveyor stops and still be
+/// flung off instead of scored (chain links)
+const EJECT_WINDOW: f64 = 3.0;
+
+/// How long a ring stays in front of the optical sensor (chain links)
+const RING_LENGTH: f64 = 3.0;
+
+/// Hues the optical sensor reads off each color of ring (degrees)
+const RED_HUE: f64 = 10.0;
+const BLUE_HUE: f64 = 210.0;
+
+/// What happened to a ring that left the conveyor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fate {
+    Scored,
+    Ejected,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ring {
+    pub color: Color,
+    /// Chain links past the optical sensor, negative until it gets there
+    position: f64,
+    /// Set once the ring leaves the conveyor
+    pub fate: Option<Fate>,
+}
+
+pub struct Conveyor {
+    optical_sensor: MockOptical,
+    /// Every ring fed in, in order, including ones that have left
+    pub rings: Vec<Ring>,
+}
+
+impl Conveyor {
+    pub fn new(optical_sensor: MockOptical) -> Self {
+        Self {
+            optical_sensor,
+            rings: Vec::new(),
+        }
+    }
+
+    /// Feeds rings into the bottom of the conveyor, `spacing` chain links apart
+    /// and behind any already on it
+    #[cfg(test)]
+    pub fn feed(&mut self, colors: &[Color], spacing: f64) {
+        let mut position = self
+            .rings
+            .iter()
+            .map(|ring| ring.position)
+            .fold(0.0, f64::min);
+        for &color in colors {
+            position -= spacing;
+            self.rings.push(Ring {
+                color,
+                position,
+                fate: None,
+            });
+        }
+    }
+
+    /// Moves the rings along by how far the intake motor turned (revolutions)
+    /// and updates what the optical sensor sees
+    pub fn step(&mut self, revolutions: f64) {
+        let travel = revolutions * SPROCKET_TEETH;
+        for ring in self.rings.iter_mut().filter(|ring| ring.fate.is_none()) {
+            let past_top = ring.position - SENSOR_TO_TOP;
+
+            // Stopping suddenly with a ring on the hooks at the top throws it off
+            if travel == 0.0 && (0.0..EJECT_WINDOW).contains(&past_top) {
+                ring.fate = Some(Fate::Ejected);
+                continue;
+            }
+
+            ring.position += travel;
+            if ring.position - SENSOR_TO_TOP >= EJECT_WINDOW {
+                ring.fate = Some(Fate::Scored);
+            }
+        }
+
+        let in_view = self
+            .rings
+            .iter()
+            .find(|ring| ring.fate.is_none() && (0.0..RING_LENGTH).contains(&ring.position));
+        let mut optical = self.optical_sensor.state();
+        match in_view {
+            Some(ring) => {
+                optical.hue = match ring.color {
+                    Color::Red => RED_HUE,
+                    Color::Blue => BLUE_HUE,
+                };
+                optical.proximity = 1.0;
+            }
+            None => optical.proximity = 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{build, platform::sleep, settings, simulate, subsystems::command::IntakeCommand};
+
+    /// Far enough apart that each ring is sorted before the next reaches the
+    /// sensor (chain links)
+    const SPREAD_OUT: f64 = 24.0;
+
+    /// As close as the sensor can still tell rings apart, so a ring reaches the
+    /// sensor while the one before it is still on its way to the top (chain
+    /// links)
+    const BACK_TO_BACK: f64 = RING_LENGTH + 2.0;
+
+    /// How long each ring is given to be carried up and sorted
+    const TIME_PER_RING: Duration = Duration::from_secs(1);
+
+    /// Opponent rings both on their own and back to back
+    const RINGS: [Color; 6] = [
+        Color::Red,
+        Color::Blue,
+        Color::Blue,
+        Color::Red,
+        Color::Red,
+        Color::Blue,
+    ];
+
+    /// Runs the rings up the intake as `alliance` and returns what happened to
+    /// each one
+    fn sort(alliance: Color, spacing: f64) -> Vec<Option<Fate>> {
+        let (mut robot, mut simulation) = build(settings(Vec::new(), alliance));
+        simulation.conveyor.feed(&RINGS, spacing);
+        robot.intake.set_command(IntakeCommand::On);
+        simulate(
+            &mut simulation,
+            sleep(TIME_PER_RING * (RINGS.len() as u32 + 1)),
+        );
+        simulation
+            .conveyor
+            .rings
+            .iter()
+            .map(|ring| ring.fate)
+            .collect()
+    }
+
+    /// Alliance rings scored and opponent rings ejected
+    fn sorted(alliance: Color) -> Vec<Option<Fate>> {
+        RINGS
+            .iter()
+            .map(|&color| {
+                Some(if color == alliance {
+                    Fate::Scored
+                } else {
+                    Fate::Ejected
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sorts_as_red() {
+        assert_eq!(sort(Color::Red, SPREAD_OUT), sorted(Color::Red));
+    }
+
+    #[test]
+    fn sorts_as_blue() {
+        assert_eq!(sort(Color::Blue, SPREAD_OUT), sorted(Color::Blue));
+    }
+
+    #[test]
+    fn sorts_back_to_back_as_red() {
+        assert_eq!(sort(Color::Red, BACK_TO_BACK), sorted(Color::Red));
+    }
+
+    #[test]
+    fn sorts_back_to_back_as_blue() {
+        assert_eq!(sort(Color::Blue, BACK_TO_BACK), sorted(Color::Blue));
+    }
+}
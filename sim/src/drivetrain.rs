@@ -0,0 +1,162 @@
+//! Physics for the differential drivetrain. Each side's three blue-cartridge
+//! motors push the robot through the gearing and wheels, and the robot speeds
+//! up, slows down and turns from the forces they make.
+
+use std::{f64::consts::PI, time::Duration};
+
+use crate::{
+    config::{GEARING, TRACK_WIDTH, WHEEL_DIAMETER},
+    hardware::{
+        BrakeMode,
+        mock::{MockImu, MockMotor},
+    },
+    pose::Vec2,
+};
+
+/// Torque a blue-cartridge V5 motor makes when stalled at 12 V (N·m). The
+/// motor's 2.1 N·m is for the 100 rpm cartridge, which is geared 6 times slower.
+const STALL_TORQUE: f64 = 2.1 / 6.0;
+
+/// Speed of an unloaded blue-cartridge motor at 12 V (rad/s)
+const FREE_SPEED: f64 = 600.0 / 60.0 * 2.0 * PI;
+
+/// Voltage the torque and speed ratings are given at
+const RATED_VOLTAGE: f64 = 12.0;
+
+/// Weight of the robot (kg), about 15 lb
+const MASS: f64 = 6.8;
+
+/// Resistance to turning around the robot's center (kg·m²), treating it as a
+/// uniform 14 by 14 inch square
+const MOMENT_OF_INERTIA: f64 = MASS * (2.0 * 0.3556 * 0.3556) / 12.0;
+
+/// Rolling resistance and drivetrain friction on each side (N)
+const FRICTION: f64 = 4.0;
+
+/// Wheel speed (m/s) under which friction fades out, so a stopped robot isn't
+/// pushed back and forth
+const FRICTION_SPEED: f64 = 0.02;
+
+const METERS_PER_INCH: f64 = 0.0254;
+
+/// Where the robot is and how fast it's moving
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct State {
+    /// Inches
+    pub position: Vec2<f64>,
+    /// Radians, counterclockwise like the robot's tracking
+    pub heading: f64,
+    /// Meters/second forwards
+    pub linear_velocity: f64,
+    /// Radians/second counterclockwise
+    pub angular_velocity: f64,
+}
+
+/// A simulated drivetrain, moved by the voltages the robot's code gives its motors
+pub struct DrivetrainModel {
+    sides: [[MockMotor; 3]; 2],
+    imu: MockImu,
+    state: State,
+    /// Heading the inertial sensor was calibrated at (radians)
+    imu_zero: f64,
+}
+
+impl DrivetrainModel {
+    /// Simulates the left and right motors and the inertial sensor, starting at
+    /// the origin facing along the x axis
+    pub fn new(sides: [[MockMotor; 3]; 2], imu: MockImu) -> Self {
+        Self {
+            sides,
+            imu,
+            state: State::default(),
+            imu_zero: 0.0,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Puts the robot somewhere on the field, stopped, with the inertial
+    /// sensor calibrated facing its heading like it is before a match
+    pub fn place(&mut self, position: Vec2<f64>, heading: f64) {
+        self.state = State {
+            position,
+            heading,
+            ..State::default()
+        };
+        self.imu_zero = heading;
+        self.update_imu();
+    }
+
+    /// Average voltage each side is being driven at
+    pub fn voltages(&self) -> [f64; 2] {
+        self.sides.each_ref().map(|motors| {
+            motors
+                .iter()
+                .map(|motor| motor.state().voltage)
+                .sum::<f64>()
+                / motors.len() as f64
+        })
+    }
+
+    /// Moves the simulation forward by `dt`
+    pub fn step(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f64();
+        let radius = WHEEL_DIAMETER / 2.0 * METERS_PER_INCH;
+        let half_track = TRACK_WIDTH / 2.0 * METERS_PER_INCH;
+        let State {
+            linear_velocity,
+            angular_velocity,
+            ..
+        } = self.state;
+
+        // Force each side's wheels push the robot with (N)
+        let forces = [-1.0, 1.0].map(|direction| {
+            let side = if direction < 0.0 { 0 } else { 1 };
+            let wheel_speed = linear_velocity + direction * angular_velocity * half_track;
+            // The motors spin faster than the wheels by the gearing
+            let motor_speed = wheel_speed / radius / GEARING;
+
+            let mut force = 0.0;
+            for motor in &self.sides[side] {
+                let mut state = motor.state();
+                // A coasting motor lets its side roll freely
+                if state.brake != Some(BrakeMode::Coast) {
+                    force += motor_torque(state.voltage, motor_speed) / GEARING / radius;
+                }
+                state.position += motor_speed * dt / (2.0 * PI);
+                state.velocity = motor_speed * 60.0 / (2.0 * PI);
+            }
+
+            force - FRICTION * (wheel_speed / FRICTION_SPEED).clamp(-1.0, 1.0)
+        });
+
+        let acceleration = (forces[0] + forces[1]) / MASS;
+        let angular_acceleration = (forces[1] - forces[0]) * half_track / MOMENT_OF_INERTIA;
+
+        let state = &mut self.state;
+        state.linear_velocity += acceleration * dt;
+        state.angular_velocity += angular_acceleration * dt;
+        state.heading += state.angular_velocity * dt;
+        state.position +=
+            Vec2::from_polar(state.linear_velocity * dt / METERS_PER_INCH, state.heading);
+        self.update_imu();
+    }
+
+    fn update_imu(&mut self) {
+        // The sensor reads clockwise degrees from 0 to 360
+        let turned = -(self.state.heading - self.imu_zero).to_degrees();
+        let mut imu = self.imu.state();
+        imu.heading = turned.rem_euclid(360.0);
+    }
+}
+
+/// Torque a motor makes at a voltage while spinning at a speed (rad/s). It
+/// falls off in a straight line from stall to free speed, and the motor's
+/// current limit keeps it under the stall torque even when driven against its
+/// spin.
+fn motor_torque(voltage: f64, speed: f64) -> f64 {
+    let torque = STALL_TORQUE * (voltage / RATED_VOLTAGE - speed / FREE_SPEED);
+    torque.clamp(-STALL_TORQUE, STALL_TORQUE)
+}
@@ -0,0 +1,377 @@
+//! Runs the robot's code on a computer against a simulated robot, for tuning
+//! the controllers and checking routines without a field. The robot's own
+//! sources are compiled here with mock devices in place of vexide's, and the
+//! robot is built by the same `Robot::new` as on the Brain, so everything runs
+//! as it would there, just on simulated devices and a simulated clock.
+//!
+//! ```text
+//! cd sim
+//! cargo run -- motion [--out trace.csv] "Pose (0,0) 0" "Drive 24" "Turn 90"
+//! cargo run -- run Skills [--color blue] [--out skills.json]
+//! cargo run -- preview [Skills...] [--color blue] [--out previews]
+//! ```
+//!
+//! `motion` runs each argument as a line of botpath, one command at a time
+//! through `execute_command`. A `Pose` first puts the robot there before it
+//! starts.
+//!
+//! `run` loads a routine from `src/autonomous/paths`, by name or by file, and
+//! runs it through `execute_path` the same way `Compete::autonomous` does.
+//! Blue runs are mirrored like the Brain's selector mirrors them.
+//!
+//! How each motion went is printed as it finishes. Where the robot was, what
+//! its mechanisms were doing, and how each motion finished are written to the
+//! trace every 10 ms, as JSON if the file ends in `.json` or CSV otherwise.
+//! Tracing a routine before and after a change shows what the change did.
+//!
+//! `preview` draws routines over the field like the Brain's selector does, as
+//! a PNG from the selector's own canvas and as an SVG, into the `--out`
+//! directory. With no routines named, every one in `src/autonomous/paths` is
+//! drawn.
+//!
+//! `cargo test` feeds rings up the intake's conveyor past its optical sensor as
+//! each alliance, and checks that the color sort ejects every opponent ring and
+//! lets every alliance ring through.
+
+extern crate alloc;
+
+use std::{
+    cell::RefCell,
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::{
+    autonomous::{
+        command::{Command, path_to_commands},
+        execute::{MotionSettings, execute_command},
+        report::Report,
+    },
+    backend::reverse::invert_coords,
+    config::{Color, RobotSettings},
+    hardware::mock::{Mock, Mocks, PneumaticState},
+    platform::Instant,
+    preview::Preview,
+    robot::{Devices, Robot},
+    simulation::Simulation,
+    trace::{SAMPLE_INTERVAL, Sample, Trace},
+};
+
+// The robot's sources, shared with the Brain build. Some of what they define is
+// only used there.
+#[allow(dead_code, unused_imports)]
+#[path = "../../src/autonomous/mod.rs"]
+mod autonomous;
+#[allow(dead_code, unused_imports)]
+#[path = "../../src/backend"]
+mod backend {
+    pub mod canvas;
+    pub mod reverse;
+}
+#[allow(dead_code)]
+#[path = "../../src/config.rs"]
+mod config;
+#[allow(dead_code)]
+#[path = "../../src/hardware/mod.rs"]
+mod hardware;
+#[allow(dead_code, unused_imports)]
+#[path = "../../src/motion/mod.rs"]
+mod motion;
+#[allow(dead_code, unused_imports)]
+#[path = "../../src/platform/mod.rs"]
+mod platform;
+#[allow(dead_code, unused_imports)]
+#[path = "../../src/pose.rs"]
+mod pose;
+#[allow(dead_code)]
+#[path = "../../src/robot.rs"]
+mod robot;
+#[allow(dead_code, unused_imports)]
+#[path = "../../src/subsystems"]
+mod subsystems {
+    pub mod command;
+    pub mod drivetrain;
+    pub mod intake;
+    pub mod lady_brown;
+}
+
+mod conveyor;
+mod drivetrain;
+mod preview;
+mod simulation;
+mod trace;
+
+/// Longest a simulation runs before giving up on the motions finishing
+const TIME_LIMIT: Duration = Duration::from_secs(60);
+
+/// Where the routines the Brain can pick from are kept
+const PATHS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../src/autonomous/paths");
+
+const USAGE: &str = "\
+usage: cargo run -- motion [--out trace.csv] <botpath line>...
+       cargo run -- run <routine> [--color red|blue] [--out trace.csv]
+       cargo run -- preview [<routine>...] [--color red|blue] [--out previews]";
+
+/// What to simulate or draw, and where to write it
+struct Options {
+    /// Each subcommand has its own default
+    out: Option<PathBuf>,
+    color: Color,
+    /// Everything that wasn't an option
+    args: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut options = Self {
+            out: None,
+            color: Color::Red,
+            args: Vec::new(),
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--out" => options.out = Some(args.next()?.into()),
+                "-c" | "--color" => {
+                    options.color = match args.next()?.as_str() {
+                        "red" => Color::Red,
+                        "blue" => Color::Blue,
+                        _ => return None,
+                    }
+                }
+                _ => options.args.push(arg),
+            }
+        }
+        Some(options)
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let subcommand = args.next();
+    let Some(options) = Options::parse(args) else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    let result = match subcommand.as_deref() {
+        Some("motion") if !options.args.is_empty() => motion(&options),
+        Some("run") if options.args.len() == 1 => run(&options),
+        Some("preview") => preview(&options),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs botpath lines one command at a time
+fn motion(options: &Options) -> Result<(), String> {
+    let commands = path_to_commands(&options.args.join("\n")).map_err(|e| e.to_string())?;
+    let (mut robot, mut simulation) = build(settings(Vec::new(), options.color));
+
+    // A starting pose places the robot, like the start of a routine does
+    let mut commands = commands.as_slice();
+    if let Some(&Command::Pose(position, angle)) = commands.first() {
+        simulation.drivetrain.place(position, angle.to_radians());
+        robot.drivetrain.tracking.set_position(position);
+        robot.drivetrain.tracking.set_heading(angle.to_radians());
+        commands = &commands[1..];
+    }
+
+    let mut report = Report::default();
+    let motions = async {
+        let mut settings = MotionSettings::default();
+        for &command in commands {
+            execute_command(&mut robot, command, &mut settings, &mut report).await;
+        }
+    };
+
+    let (samples, finished) = simulate(&mut simulation, motions);
+    let trace = Trace {
+        name: options.args.join("; "),
+        samples,
+        results: report.results,
+        aborted: false,
+        timed_out: finished.is_none(),
+    };
+    finish(&trace, options)
+}
+
+/// Runs a routine the way the Brain runs it in autonomous
+fn run(options: &Options) -> Result<(), String> {
+    let routine = &options.args[0];
+    let commands = load_routine(routine, options.color)?;
+
+    // The robot is set down where the routine starts before autonomous begins
+    let start = commands.first().copied();
+    let (mut robot, mut simulation) = build(settings(commands, options.color));
+    if let Some(Command::Pose(position, angle)) = start {
+        simulation.drivetrain.place(position, angle.to_radians());
+    }
+
+    let mut report = Report::default();
+    let (samples, finished) = simulate(&mut simulation, robot.run_auton(&mut report));
+    println!("{report}");
+
+    let trace = Trace {
+        name: routine.clone(),
+        samples,
+        results: report.results,
+        aborted: report.aborted,
+        timed_out: finished.is_none(),
+    };
+    finish(&trace, options)
+}
+
+/// Draws routines, or all of them if none are named
+fn preview(options: &Options) -> Result<(), String> {
+    let routines = if options.args.is_empty() {
+        routines()?
+    } else {
+        options.args.clone()
+    };
+    let out = options.out.clone().unwrap_or_else(|| "previews".into());
+    fs::create_dir_all(&out)
+        .map_err(|error| format!("couldn't make {}: {error}", out.display()))?;
+
+    for routine in &routines {
+        let preview = Preview::new(&load_routine(routine, options.color)?, options.color);
+        let name = Path::new(routine)
+            .file_stem()
+            .map_or(routine.clone(), |name| name.to_string_lossy().into_owned());
+
+        let png = out.join(format!("{name}.png"));
+        preview
+            .write_png(&png)
+            .map_err(|error| format!("couldn't write {}: {error}", png.display()))?;
+        let svg = out.join(format!("{name}.svg"));
+        preview
+            .write_svg(&svg)
+            .map_err(|error| format!("couldn't write {}: {error}", svg.display()))?;
+        println!("Drew {routine} to {} and {}", png.display(), svg.display());
+    }
+    Ok(())
+}
+
+/// Names of the routines the Brain can pick from
+fn routines() -> Result<Vec<String>, String> {
+    let entries =
+        fs::read_dir(PATHS_DIR).map_err(|error| format!("couldn't read {PATHS_DIR}: {error}"))?;
+    let mut routines: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let is_botpath = path.extension().is_some_and(|ext| ext == "botpath");
+            is_botpath.then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    routines.sort();
+    Ok(routines)
+}
+
+/// Reads a routine by name from `src/autonomous/paths`, or from a file, mirrored
+/// for blue the way the Brain's selector mirrors it
+fn load_routine(routine: &str, color: Color) -> Result<Vec<Command>, String> {
+    let file = match PathBuf::from(routine) {
+        file if file.is_file() => file,
+        _ => PathBuf::from(format!("{PATHS_DIR}/{routine}.botpath")),
+    };
+    let path = fs::read_to_string(&file)
+        .map_err(|error| format!("couldn't read {}: {error}", file.display()))?;
+    let commands = path_to_commands(&path).map_err(|e| e.to_string())?;
+    Ok(match color {
+        Color::Red => commands,
+        Color::Blue => invert_coords(&commands),
+    })
+}
+
+/// Builds the robot the way `main` does out of mock devices, along with the
+/// simulation that moves them
+fn build(settings: Rc<RefCell<RobotSettings>>) -> (Robot<Mocks>, Simulation) {
+    // The solenoids start out the way `main` sets them
+    let extended = || {
+        Mock::new(PneumaticState {
+            extended: true,
+            ..PneumaticState::default()
+        })
+    };
+    let devices = Devices::<Mocks> {
+        imu: Mock::default(),
+        left_motors: Default::default(),
+        right_motors: Default::default(),
+        intake_motor: Mock::default(),
+        optical_sensor: Mock::default(),
+        doinker: extended(),
+        lady_brown_motor: Mock::default(),
+        lady_brown_rotation: Mock::default(),
+        clamp: (Mock::default(), extended()),
+    };
+
+    let simulation = Simulation::new(&devices);
+    let robot = platform::run(Robot::new(devices, settings), TIME_LIMIT, |_| ())
+        .expect("the robot's devices should set up straight away");
+    (robot, simulation)
+}
+
+fn settings(auton_path: Vec<Command>, color: Color) -> Rc<RefCell<RobotSettings>> {
+    Rc::new(RefCell::new(RobotSettings {
+        auton_path,
+        test_auton: false,
+        curr_color: color,
+    }))
+}
+
+/// Runs the robot's code alongside the simulation, sampling it as it goes.
+/// Returns `None` instead of the output if the time limit was hit first.
+fn simulate<T>(
+    simulation: &mut Simulation,
+    robot: impl Future<Output = T>,
+) -> (Vec<Sample>, Option<T>) {
+    let start = Instant::now();
+    let mut samples = Vec::new();
+    let mut next_sample = Duration::ZERO;
+    let output = platform::run(robot, TIME_LIMIT, |dt| {
+        simulation.step(dt);
+        let time = start.elapsed();
+        if time >= next_sample {
+            samples.push(Sample::of(time, simulation));
+            next_sample += SAMPLE_INTERVAL;
+        }
+    });
+    (samples, output)
+}
+
+/// Says where the robot ended up and writes out the trace
+fn finish(trace: &Trace, options: &Options) -> Result<(), String> {
+    if trace.timed_out {
+        eprintln!(
+            "warning: stopped after {}s without finishing",
+            TIME_LIMIT.as_secs()
+        );
+    }
+
+    if let Some(end) = trace.samples.last() {
+        println!(
+            "Ended at ({:.2}, {:.2}) facing {:.2}° after {:.2}s",
+            end.position.x(),
+            end.position.y(),
+            end.heading,
+            end.time.as_secs_f64(),
+        );
+    }
+
+    let out = options.out.clone().unwrap_or_else(|| "trace.csv".into());
+    trace
+        .write(&out)
+        .map_err(|error| format!("couldn't write {}: {error}", out.display()))?;
+    println!("Wrote {} samples to {}", trace.samples.len(), out.display());
+    Ok(())
+}
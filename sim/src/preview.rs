@@ -0,0 +1,196 @@
+//! Pictures of routines like the ones the Brain's selector shows, for sharing
+//! without the robot
+
+use std::{fmt::Write as _, fs, path::Path};
+
+use resvg::{
+    tiny_skia::{Pixmap, PremultipliedColorU8, Transform},
+    usvg::{Options, Tree},
+};
+
+use crate::{
+    autonomous::command::{Command, command_to_coords},
+    backend::canvas::Canvas,
+    config::{Color, TRACK_WIDTH},
+    pose::Vec2,
+};
+
+/// The field drawn under the path on the Brain
+const FIELD: &str = include_str!("../../images/match_field.svg");
+
+/// Width of the field, which is also the size of the Brain's canvas (inches)
+const FIELD_SIZE: u32 = 144;
+
+/// How many PNG pixels each canvas pixel becomes, so small previews are still
+/// readable
+const PNG_SCALE: u32 = 4;
+
+/// Length of the dashes and gaps in moves driven in reverse, matching the
+/// Brain's canvas (inches)
+const DASH: f64 = 3.0;
+
+/// A routine ready to be drawn
+pub struct Preview {
+    /// The routine's path as coordinates and curves
+    coords: Vec<Command>,
+    /// Where the robot starts and which way it faces, if the routine says
+    start: Option<(Vec2<f64>, f64)>,
+    color: Color,
+}
+
+impl Preview {
+    pub fn new(commands: &[Command], color: Color) -> Self {
+        Self {
+            coords: command_to_coords(commands, TRACK_WIDTH),
+            start: match commands.first() {
+                Some(&Command::Pose(position, heading)) => Some((position, heading)),
+                _ => None,
+            },
+            color,
+        }
+    }
+
+    /// Writes the preview drawn by the Brain's own canvas over the field, as
+    /// the selector shows it
+    pub fn write_png(&self, path: &Path) -> Result<(), String> {
+        let mut canvas = Canvas::new(FIELD_SIZE, FIELD_SIZE, self.color);
+        canvas.draw_commands(&self.coords);
+        if let Some((position, heading)) = self.start {
+            canvas.draw_pose(position, heading);
+        }
+
+        let size = FIELD_SIZE * PNG_SCALE;
+        let mut pixmap = Pixmap::new(size, size).ok_or("couldn't make the image")?;
+        let field = field()?;
+        resvg::render(
+            &field,
+            Transform::from_scale(
+                size as f32 / field.size().width(),
+                size as f32 / field.size().height(),
+            ),
+            &mut pixmap.as_mut(),
+        );
+
+        // Drawn pixels are fully opaque, and everything else is left showing
+        // the field
+        let width = canvas.width() as usize;
+        let image_width = pixmap.width() as usize;
+        let pixels = pixmap.pixels_mut();
+        for (i, rgba) in canvas.pixels().chunks_exact(4).enumerate() {
+            if rgba[3] == 0 {
+                continue;
+            }
+            let color = PremultipliedColorU8::from_rgba(rgba[0], rgba[1], rgba[2], 255)
+                .ok_or("couldn't convert the path's color")?;
+
+            let (x, y) = (i % width, i / width);
+            let scale = PNG_SCALE as usize;
+            for row in y * scale..(y + 1) * scale {
+                let start = row * image_width + x * scale;
+                pixels[start..start + scale].fill(color);
+            }
+        }
+
+        pixmap.save_png(path).map_err(|error| error.to_string())
+    }
+
+    /// Writes the preview as vector lines and curves over the field
+    pub fn write_svg(&self, path: &Path) -> Result<(), String> {
+        let field = field()?;
+        let color = match self.color {
+            Color::Red => "#ff0000",
+            Color::Blue => "#0000ff",
+        };
+        let size = FIELD_SIZE * PNG_SCALE;
+
+        let mut svg = String::new();
+        _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+             viewBox=\"0 0 {FIELD_SIZE} {FIELD_SIZE}\">"
+        );
+        _ = writeln!(
+            svg,
+            "<g transform=\"scale({} {})\">\n{}\n</g>",
+            FIELD_SIZE as f32 / field.size().width(),
+            FIELD_SIZE as f32 / field.size().height(),
+            FIELD.trim(),
+        );
+        _ = writeln!(
+            svg,
+            "<g fill=\"none\" stroke=\"{color}\" stroke-width=\"1\" stroke-linecap=\"round\">"
+        );
+
+        let mut prev: Option<Vec2<f64>> = None;
+        for command in &self.coords {
+            match *command {
+                Command::Coordinate(coord, reverse) => {
+                    if let Some(prev) = prev {
+                        _ = writeln!(
+                            svg,
+                            "<path d=\"M {} {} L {} {}\"{}/>",
+                            prev.x(),
+                            prev.y(),
+                            coord.x(),
+                            coord.y(),
+                            dashes(reverse),
+                        );
+                    }
+                    prev = Some(coord);
+                }
+                Command::CubicBezier(p0, p1, p2, p3, reverse) => {
+                    _ = writeln!(
+                        svg,
+                        "<path d=\"M {} {} C {} {} {} {} {} {}\"{}/>",
+                        p0.x(),
+                        p0.y(),
+                        p1.x(),
+                        p1.y(),
+                        p2.x(),
+                        p2.y(),
+                        p3.x(),
+                        p3.y(),
+                        dashes(reverse),
+                    );
+                    prev = Some(p3);
+                }
+                _ => (),
+            }
+        }
+
+        if let Some((position, heading)) = self.start {
+            let facing = position + Vec2::from_polar(6.0, heading.to_radians());
+            _ = writeln!(
+                svg,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"1.5\" fill=\"{color}\"/>",
+                position.x(),
+                position.y(),
+            );
+            _ = writeln!(
+                svg,
+                "<path d=\"M {} {} L {} {}\"/>",
+                position.x(),
+                position.y(),
+                facing.x(),
+                facing.y(),
+            );
+        }
+
+        _ = writeln!(svg, "</g>\n</svg>");
+        fs::write(path, svg).map_err(|error| error.to_string())
+    }
+}
+
+fn field() -> Result<Tree, String> {
+    Tree::from_str(FIELD, &Options::default())
+        .map_err(|error| format!("couldn't read the field image: {error}"))
+}
+
+/// Dashes moves driven in reverse, like the Brain's canvas does
+fn dashes(reverse: bool) -> String {
+    if reverse {
+        format!(" stroke-dasharray=\"{DASH} {DASH}\"")
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,97 @@
+//! Everything on the simulated robot that moves, stepped forward together
+
+use std::time::Duration;
+
+use crate::{
+    conveyor::Conveyor,
+    drivetrain::DrivetrainModel,
+    hardware::mock::{MockMotor, MockPneumatic, MockRotation, Mocks},
+    robot::Devices,
+};
+
+/// Free speed of the intake's blue cartridge and the lady brown's red one (rpm)
+const INTAKE_RPM: f64 = 600.0;
+const LADY_BROWN_RPM: f64 = 100.0;
+
+/// Angle the lady brown rests at and can't fall past (degrees)
+const LADY_BROWN_REST: f64 = 0.0;
+
+/// The physics behind the robot's devices. The robot's code drives the
+/// devices, and the simulation moves them in response.
+pub struct Simulation {
+    pub drivetrain: DrivetrainModel,
+    pub intake: MockMotor,
+    /// Carries rings past the intake's optical sensor
+    pub conveyor: Conveyor,
+    pub lady_brown: MockMotor,
+    pub lady_brown_rotation: MockRotation,
+    /// Both clamp solenoids switch together, so one is enough to watch
+    pub clamp: MockPneumatic,
+}
+
+impl Simulation {
+    /// Simulates the devices the robot is built from. The devices share their
+    /// state with these, so the robot sees everything the simulation does.
+    pub fn new(devices: &Devices<Mocks>) -> Self {
+        Self {
+            drivetrain: DrivetrainModel::new(
+                [devices.left_motors.clone(), devices.right_motors.clone()],
+                devices.imu.clone(),
+            ),
+            intake: devices.intake_motor.clone(),
+            conveyor: Conveyor::new(devices.optical_sensor.clone()),
+            lady_brown: devices.lady_brown_motor.clone(),
+            lady_brown_rotation: devices.lady_brown_rotation.clone(),
+            clamp: devices.clamp.0.clone(),
+        }
+    }
+
+    /// Moves everything forward by `dt`
+    pub fn step(&mut self, dt: Duration) {
+        self.drivetrain.step(dt);
+
+        // Mechanisms are light enough next to their motors to treat as
+        // reaching speed straight away
+        let revolutions = spin(&self.intake, INTAKE_RPM, dt);
+        self.conveyor.step(revolutions);
+
+        let revolutions = spin(&self.lady_brown, LADY_BROWN_RPM, dt);
+        let mut rotation = self.lady_brown_rotation.state();
+        rotation.position = (rotation.position + revolutions).max(LADY_BROWN_REST / 360.0);
+    }
+
+    /// Voltage the intake is driven at
+    pub fn intake_voltage(&self) -> f64 {
+        self.intake.state().voltage
+    }
+
+    /// Angle of the lady brown arm (degrees)
+    pub fn lady_brown_angle(&self) -> f64 {
+        self.lady_brown_rotation.state().position * 360.0
+    }
+
+    /// Voltage the lady brown is driven at
+    pub fn lady_brown_voltage(&self) -> f64 {
+        self.lady_brown.state().voltage
+    }
+
+    pub fn clamped(&self) -> bool {
+        self.clamp.state().extended
+    }
+}
+
+/// Spins a motor with no load at a speed proportional to its voltage, up to
+/// its free speed (rpm), stopping it when it's braking. Returns how far it
+/// turned (revolutions).
+fn spin(motor: &MockMotor, max_rpm: f64, dt: Duration) -> f64 {
+    let mut state = motor.state();
+    state.velocity = if state.brake.is_some() {
+        0.0
+    } else {
+        max_rpm * state.voltage / state.max_voltage
+    };
+
+    let revolutions = state.velocity / 60.0 * dt.as_secs_f64();
+    state.position += revolutions;
+    revolutions
+}
@@ -0,0 +1,196 @@
+//! What the simulated robot did over a run, written out for plotting or for
+//! diffing one run against another
+
+use std::{fmt::Write as _, fs, io, path::Path, time::Duration};
+
+use crate::{autonomous::report::MotionResult, pose::Vec2, simulation::Simulation};
+
+/// How often the robot's state is recorded
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    /// Since the start of the run
+    pub time: Duration,
+    /// Inches
+    pub position: Vec2<f64>,
+    /// Degrees clockwise, like `Turn` in a botpath, from -180 to 180
+    pub heading: f64,
+    /// Voltage the left and right drive motors were driven at
+    pub voltages: [f64; 2],
+    pub intake_voltage: f64,
+    /// Degrees
+    pub lady_brown_angle: f64,
+    pub lady_brown_voltage: f64,
+    pub clamped: bool,
+}
+
+impl Sample {
+    pub fn of(time: Duration, simulation: &Simulation) -> Self {
+        let state = simulation.drivetrain.state();
+        Self {
+            time,
+            position: state.position,
+            // Counterclockwise radians from the model, turned into the botpath's degrees
+            heading: ((180.0 - state.heading.to_degrees()).rem_euclid(360.0)) - 180.0,
+            voltages: simulation.drivetrain.voltages(),
+            intake_voltage: simulation.intake_voltage(),
+            lady_brown_angle: simulation.lady_brown_angle(),
+            lady_brown_voltage: simulation.lady_brown_voltage(),
+            clamped: simulation.clamped(),
+        }
+    }
+}
+
+/// Everything recorded over one run
+pub struct Trace {
+    /// Name of what was run, like the routine's file name
+    pub name: String,
+    pub samples: Vec<Sample>,
+    /// How each motion went, in the order they finished
+    pub results: Vec<MotionResult>,
+    /// Set if a failure stopped the path early
+    pub aborted: bool,
+    /// Set if the run was cut off by the time limit
+    pub timed_out: bool,
+}
+
+impl Trace {
+    /// Writes the trace as JSON if the file ends in `.json`, or CSV otherwise
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let contents = if path.extension().is_some_and(|ext| ext == "json") {
+            self.to_json()
+        } else {
+            self.to_csv()
+        };
+        fs::write(path, contents)
+    }
+
+    /// One row per sample. Each row names the motion running at the time, and
+    /// the first row after a motion finishes says how it went.
+    fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "time,x,y,heading,left_voltage,right_voltage,intake_voltage,\
+             lady_brown_angle,lady_brown_voltage,clamped,motion,outcome\n",
+        );
+
+        let mut finished = self.results.iter().peekable();
+        let mut last_time = Duration::ZERO;
+        for sample in &self.samples {
+            let running = self.results.iter().find(|result| {
+                result.started <= sample.time && sample.time < result.started + result.duration
+            });
+            let mut outcomes = Vec::new();
+            while let Some(result) =
+                finished.next_if(|result| result.started + result.duration <= sample.time)
+            {
+                // Motions that finish between samples are reported on the next one
+                if result.started + result.duration >= last_time {
+                    outcomes.push(result.outcome.to_string());
+                }
+            }
+            last_time = sample.time;
+
+            _ = writeln!(
+                csv,
+                "{:.3},{:.3},{:.3},{:.2},{:.3},{:.3},{:.3},{:.2},{:.3},{},{},{}",
+                sample.time.as_secs_f64(),
+                sample.position.x(),
+                sample.position.y(),
+                sample.heading,
+                sample.voltages[0],
+                sample.voltages[1],
+                sample.intake_voltage,
+                sample.lady_brown_angle,
+                sample.lady_brown_voltage,
+                sample.clamped,
+                csv_field(
+                    &running
+                        .map(|result| format!("{:?}", result.command))
+                        .unwrap_or_default()
+                ),
+                outcomes.join(" "),
+            );
+        }
+        csv
+    }
+
+    fn to_json(&self) -> String {
+        let mut json = String::new();
+        _ = writeln!(json, "{{");
+        _ = writeln!(json, "  \"name\": {},", json_string(&self.name));
+        _ = writeln!(json, "  \"aborted\": {},", self.aborted);
+        _ = writeln!(json, "  \"timed_out\": {},", self.timed_out);
+
+        _ = writeln!(json, "  \"results\": [");
+        for (i, result) in self.results.iter().enumerate() {
+            _ = write!(
+                json,
+                "    {{\"command\": {}, \"outcome\": {}, \"error\": {:.3}, \"started\": {:.3}, \"duration\": {:.3}}}",
+                json_string(&format!("{:?}", result.command)),
+                json_string(&result.outcome.to_string()),
+                result.error,
+                result.started.as_secs_f64(),
+                result.duration.as_secs_f64(),
+            );
+            _ = writeln!(
+                json,
+                "{}",
+                if i + 1 < self.results.len() { "," } else { "" }
+            );
+        }
+        _ = writeln!(json, "  ],");
+
+        _ = writeln!(json, "  \"samples\": [");
+        for (i, sample) in self.samples.iter().enumerate() {
+            _ = write!(
+                json,
+                "    {{\"time\": {:.3}, \"x\": {:.3}, \"y\": {:.3}, \"heading\": {:.2}, \
+                 \"left_voltage\": {:.3}, \"right_voltage\": {:.3}, \"intake_voltage\": {:.3}, \
+                 \"lady_brown_angle\": {:.2}, \"lady_brown_voltage\": {:.3}, \"clamped\": {}}}",
+                sample.time.as_secs_f64(),
+                sample.position.x(),
+                sample.position.y(),
+                sample.heading,
+                sample.voltages[0],
+                sample.voltages[1],
+                sample.intake_voltage,
+                sample.lady_brown_angle,
+                sample.lady_brown_voltage,
+                sample.clamped,
+            );
+            _ = writeln!(
+                json,
+                "{}",
+                if i + 1 < self.samples.len() { "," } else { "" }
+            );
+        }
+        _ = writeln!(json, "  ]");
+        _ = writeln!(json, "}}");
+        json
+    }
+}
+
+/// Quotes a CSV field if it has anything that would split it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => _ = write!(quoted, "\\u{:04x}", c as u32),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,128 @@
+//! Feedback drives and turns, steered with a linear and an angular PID on the
+//! drivetrain's tracking until both settle or the motion runs out of time
+
+use core::{f64::consts::PI, time::Duration};
+
+use crate::{
+    config::MAX_VOLTAGE,
+    hardware::MotorDevice,
+    motion::{
+        pid::{Pid, Tolerances},
+        wrap_angle,
+    },
+    platform::{Float, Instant, sleep},
+    pose::Vec2,
+    subsystems::drivetrain::Drivetrain,
+};
+
+/// How often the controllers are updated
+const UPDATE_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Basic {
+    pub linear_controller: Pid,
+    pub angular_controller: Pid,
+    pub linear_tolerances: Tolerances,
+    pub angular_tolerances: Tolerances,
+    pub timeout: Duration,
+}
+
+impl Basic {
+    /// Drives straight forward (or backward if negative) by a distance (inches)
+    /// while holding the heading the robot started at
+    pub async fn drive_distance(&mut self, dt: &mut Drivetrain<impl MotorDevice>, distance: f64) {
+        let heading = dt.tracking.heading();
+        self.drive_distance_at_heading(dt, distance, heading).await;
+    }
+
+    /// Turns in place to a heading (radians, counterclockwise)
+    pub async fn turn_to_heading(&mut self, dt: &mut Drivetrain<impl MotorDevice>, heading: f64) {
+        self.drive_distance_at_heading(dt, 0.0, heading).await;
+    }
+
+    /// Drives by a distance (inches) while turning to face a heading (radians,
+    /// counterclockwise)
+    pub async fn drive_distance_at_heading(
+        &mut self,
+        dt: &mut Drivetrain<impl MotorDevice>,
+        distance: f64,
+        heading: f64,
+    ) {
+        let target = dt.tracking.forward_travel() + distance;
+
+        let start = Instant::now();
+        let mut last_update = start;
+        while start.elapsed() < self.timeout {
+            sleep(UPDATE_INTERVAL).await;
+            let elapsed = last_update.elapsed();
+            last_update = Instant::now();
+
+            let linear_error = target - dt.tracking.forward_travel();
+            let angular_error = wrap_angle(heading - dt.tracking.heading());
+
+            // Both are checked every update so each keeps its own time in tolerance
+            let linear_settled = self
+                .linear_tolerances
+                .check(linear_error, dt.tracking.linear_velocity());
+            let angular_settled = self
+                .angular_tolerances
+                .check(angular_error, dt.tracking.angular_velocity());
+            if linear_settled && angular_settled {
+                break;
+            }
+
+            let linear = self.linear_controller.update(linear_error, elapsed);
+            let angular = self.angular_controller.update(angular_error, elapsed);
+            _ = dt.set_voltages(normalized((linear - angular, linear + angular)));
+        }
+
+        _ = dt.set_voltages((0.0, 0.0));
+    }
+
+    /// Drives to a point, turning to face it on the way. A point behind the
+    /// robot is backed into.
+    pub async fn move_to_point(&mut self, dt: &mut Drivetrain<impl MotorDevice>, point: Vec2<f64>) {
+        let start = Instant::now();
+        let mut last_update = start;
+        while start.elapsed() < self.timeout {
+            sleep(UPDATE_INTERVAL).await;
+            let elapsed = last_update.elapsed();
+            last_update = Instant::now();
+
+            let position = dt.tracking.position();
+            let offset = Vec2::new(point.x() - position.x(), point.y() - position.y());
+            let distance = offset.x().hypot(offset.y());
+            if self
+                .linear_tolerances
+                .check(distance, dt.tracking.linear_velocity())
+            {
+                break;
+            }
+
+            // Slows down while facing away from the point, and goes backward once it's behind
+            let angle_error = wrap_angle(offset.angle() - dt.tracking.heading());
+            let linear = self.linear_controller.update(distance, elapsed) * angle_error.cos();
+            let angular_error = if angle_error.abs() > PI / 2.0 {
+                wrap_angle(angle_error + PI)
+            } else {
+                angle_error
+            };
+            let angular = self.angular_controller.update(angular_error, elapsed);
+
+            _ = dt.set_voltages(normalized((linear - angular, linear + angular)));
+        }
+
+        _ = dt.set_voltages((0.0, 0.0));
+    }
+}
+
+/// Scales both sides down together so neither is asked for more than the
+/// motors can give, keeping the turn the same shape
+fn normalized((left, right): (f64, f64)) -> (f64, f64) {
+    let largest = left.abs().max(right.abs());
+    if largest > MAX_VOLTAGE {
+        (left / largest * MAX_VOLTAGE, right / largest * MAX_VOLTAGE)
+    } else {
+        (left, right)
+    }
+}
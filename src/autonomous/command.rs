@@ -1,23 +1,48 @@
-use alloc::vec::Vec;
-
-use evian::math::Vec2;
-
-use super::parse;
-use crate::{pose::Pose, subsystems::{intake::IntakeCommand, lady_brown::LadyBrownCommand}};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{mem, slice};
 
+use super::{
+    error::{ArgError, ParseError},
+    fragments,
+    parse::{self, Line, Variables},
+};
+use crate::{
+    pose::{Pose, Vec2},
+    subsystems::command::{IntakeCommand, LadyBrownCommand},
+};
 
 /// Represents different types of movement and action commands
 /// the robot will execute during the autonomous period.
 #[derive(Clone, Copy, Debug)]
 pub enum Command {
     /// Move to a specific coordinate on the field
-    Coordinate(Vec2<f64>),
+    Coordinate(Vec2<f64>, bool),
 
     /// Follow a cubic Bezier curve with four control points
-    CubicBezier(Vec2<f64>, Vec2<f64>, Vec2<f64>, Vec2<f64>),
+    CubicBezier(Vec2<f64>, Vec2<f64>, Vec2<f64>, Vec2<f64>, bool),
+
+    /// Drive to a point and arrive facing the given angle. The robot curves
+    /// in by chasing a point led out in front of the goal, `.2` of the way
+    /// back along the final heading (a default is used if not given).
+    MoveToPose(Vec2<f64>, f64, Option<f64>, bool),
 
-    /// Move forward by a specified distance (in inches)
-    DriveBy(f64),
+    /// Turn to an absolute angle by driving one side while holding the other
+    /// side still, pivoting around the held wheels
+    SwingTo(f64, Side),
+
+    /// Turn to an absolute angle while driving forward around a circle with
+    /// the given radius (inches), curving towards the new heading
+    ArcTo(f64, f64),
+
+    /// Move forward by a specified distance (in inches), following a motion
+    /// profile if one is given
+    DriveBy(f64, Option<Profile>),
 
     /// Set the initial robot position and orientation
     Pose(Vec2<f64>, f64),
@@ -25,12 +50,18 @@ pub enum Command {
     /// Rotate the robot by a relative angle
     TurnBy(f64),
 
-    /// Rotate the robot to an absolute angle
-    TurnTo(f64),
+    /// Rotate the robot to an absolute angle, following a motion profile if
+    /// one is given
+    TurnTo(f64, Option<Profile>),
 
-    /// Set a speed limit for the robot
+    /// Limit drives, point and curve moves to a fraction of top speed for
+    /// the rest of the path
     Speed(f64),
 
+    /// Limit turns, swings and arcs to a fraction of top speed for the rest
+    /// of the path
+    TurnSpeed(f64),
+
     /// Pause execution for a given duration (milliseconds)
     Sleep(u64),
 
@@ -43,60 +74,758 @@ pub enum Command {
 
     /// Toggle the mobile goal clamp
     ToggleClamp,
+
+    /// Wait until the condition is met, or until the timeout (milliseconds) runs out
+    WaitUntil(Condition, u64),
+
+    /// Continue from the command at the given index
+    Jump(usize),
+
+    /// Continue from the command at the given index unless the condition is met
+    JumpUnless(Condition, usize),
+
+    /// Run the next `.0` commands, which move the robot, at the same time as
+    /// the `.1` commands after them, which run its mechanisms
+    Together(usize, usize),
+
+    /// Drive through the next `.2` commands, which are all `Coordinate`s and
+    /// `Bezier`s, without stopping. The robot aims `.0` inches ahead along the
+    /// path and drives at up to `.1` of its top speed.
+    Pursuit(f64, f64, usize),
+
+    /// Run the action right after this once the robot is far enough along the
+    /// motion that follows the markers
+    Marker(Progress),
+
+    /// Change how the next motion runs, for that motion only. Any markers for
+    /// the motion sit between this and the motion itself.
+    With(Modifiers),
+
+    /// Choose what happens when a motion after this doesn't reach its target
+    OnFail(Failure),
+
+    /// The end of the path. The commands after this are the fallback section,
+    /// which only runs when a motion fails under `onfail fallback`.
+    Fallback,
+}
+
+/// What a path does when a motion times out, stalls or loses a motor
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Failure {
+    /// Carry on with the next command
+    #[default]
+    Continue,
+
+    /// Stop the path where it is
+    Abort,
+
+    /// Skip to the path's `fallback { ... }` section
+    Fallback,
+}
+
+/// Overrides for a single motion, written after it like `Drive 24 timeout=800`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Modifiers {
+    /// How long the motion can run before giving up (milliseconds)
+    pub timeout: Option<u64>,
+
+    /// Fraction of the robot's top speed to cap the motion at
+    pub speed: Option<f64>,
+
+    /// How close the motion has to get to finish (inches, or degrees for turns)
+    pub tolerance: Option<f64>,
+}
+
+/// One side of the drivetrain
+#[derive(Clone, Copy, Debug)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    /// The other side, for mirroring a path across the field
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+/// The shape of a motion profile for a straight drive or turn
+#[derive(Clone, Copy, Debug)]
+pub enum Profile {
+    /// Speed up and slow down at a constant rate
+    Trapezoidal,
+
+    /// Ease in and out of speeding up and slowing down, for less jerk
+    SCurve,
+}
+
+/// How far along a motion a marker fires
+#[derive(Clone, Copy, Debug)]
+pub enum Progress {
+    /// A fraction of the motion's total length, from 0.0 to 1.0
+    Fraction(f64),
+
+    /// Distance traveled since the start of the motion (inches)
+    FromStart(f64),
+
+    /// Distance left before the end of the motion (inches)
+    FromEnd(f64),
+}
+
+impl Progress {
+    /// Distance along a motion of the given length (inches) where the marker sits
+    pub fn distance(self, length: f64) -> f64 {
+        match self {
+            Progress::Fraction(fraction) => fraction * length,
+            Progress::FromStart(distance) => distance,
+            Progress::FromEnd(distance) => length - distance,
+        }
+    }
+}
+
+/// Robot state a path can branch on with `if` or wait for with `WaitUntil`,
+/// checked while the path runs
+#[derive(Clone, Copy, Debug)]
+pub enum Condition {
+    /// The mobile goal clamp is closed
+    Clamped,
+
+    /// The intake's optical sensor sees a ring of our alliance's color
+    AllianceRing,
+
+    /// The intake's optical sensor sees a ring of the opponent's color
+    OpponentRing,
+
+    /// The intake's optical sensor sees a ring of either color
+    AnyRing,
+
+    /// The intake's optical sensor doesn't see a ring
+    NoRing,
+
+    /// The drivetrain has come to a stop
+    Settled,
+
+    /// The lady brown is raised past the given angle (degrees)
+    LadyBrownAbove(f64),
+
+    /// The lady brown is lowered past the given angle (degrees)
+    LadyBrownBelow(f64),
+
+    /// More than the given time (milliseconds) has passed since the path started
+    ElapsedOver(u64),
+
+    /// Less than the given time (milliseconds) has passed since the path started
+    ElapsedUnder(u64),
+
+    /// The last motion didn't reach its target
+    Failed,
 }
 
 impl Command {
-    pub fn from_str(command: &str, args: &[&str]) -> Result<Self, &'static str> {
+    /// Checks if the command moves the drivetrain or changes how it moves
+    pub fn is_movement(&self) -> bool {
+        matches!(
+            self,
+            Command::Coordinate(..)
+                | Command::CubicBezier(..)
+                | Command::MoveToPose(..)
+                | Command::DriveBy(..)
+                | Command::Pose(..)
+                | Command::TurnBy(_)
+                | Command::TurnTo(..)
+                | Command::SwingTo(..)
+                | Command::ArcTo(..)
+                | Command::Speed(_)
+                | Command::TurnSpeed(_)
+                | Command::Pursuit(..)
+                | Command::With(_)
+        )
+    }
+
+    /// Checks if the command runs one of the robot's mechanisms
+    pub fn is_action(&self) -> bool {
+        matches!(
+            self,
+            Command::IntakeCommand(_)
+                | Command::LadyBrownCommand(_)
+                | Command::NextLBStage
+                | Command::ToggleClamp
+        )
+    }
+
+    pub fn from_str(command: &str, args: &[&str]) -> Result<Self, ArgError> {
         use parse::*;
         match command.trim() {
-            "Coordinate" => single_vec2(args).map(Command::Coordinate),
+            "Coordinate" => {
+                let (args, reverse) = reverse_flag(args);
+                single_vec2(args).map(|point| Command::Coordinate(point, reverse))
+            }
             "Bezier" => {
-                multiple_vec2(args, 4).map(|v| Command::CubicBezier(v[0], v[1], v[2], v[3]))
+                let (args, reverse) = reverse_flag(args);
+                multiple_vec2(args, 4)
+                    .map(|v| Command::CubicBezier(v[0], v[1], v[2], v[3], reverse))
+            }
+            "Drive" => {
+                profiled_f64(args).map(|(distance, profile)| Command::DriveBy(distance, profile))
             }
-            "Drive" => single_f64(args).map(Command::DriveBy),
             "Pose" => pose(args),
-            "Turn" => single_f64(args).map(Command::TurnTo),
+            "MoveToPose" => {
+                let (args, reverse) = reverse_flag(args);
+                move_to_pose(args).map(|(position, angle, lead)| {
+                    Command::MoveToPose(position, angle, lead, reverse)
+                })
+            }
+            "SwingTo" => swing(args),
+            "ArcTo" => arc(args),
+            "Turn" => profiled_f64(args).map(|(angle, profile)| Command::TurnTo(angle, profile)),
             "Sleep" => single_u64(args).map(Command::Sleep),
-            "Speed" => single_f64(args).map(Command::Speed),
+            "Speed" => single_speed(args).map(Command::Speed),
+            "TurnSpeed" => single_speed(args).map(Command::TurnSpeed),
             "Intake" => intake_command(args).map(Command::IntakeCommand),
             "LadyBrown" => lady_brown_command(args).map(Command::LadyBrownCommand),
             "NextLBStage" => Ok(Command::NextLBStage),
             "ToggleClamp" => Ok(Command::ToggleClamp),
-            _ => Err("Invalid Command"),
+            "WaitUntil" => {
+                wait_until(args).map(|(condition, timeout)| Command::WaitUntil(condition, timeout))
+            }
+            _ => Err(ArgError::invalid("a command", command)),
         }
     }
+
+    /// Checks if a name belongs to a command or keyword, which a macro can't take
+    fn is_reserved(name: &str) -> bool {
+        // Every command but an unknown one parses or fails on its arguments
+        KEYWORDS.contains(&name)
+            || Command::from_str(name, &[]).map_or_else(|e| e.expected != "a command", |_| true)
+    }
 }
 
-/// Converts a path string into a list of commands
-pub fn path_to_commands(path: &str) -> Result<Vec<Command>, &'static str> {
-    let mut commands = Vec::new();
+/// How many macro calls and includes can be nested inside each other before
+/// it's treated as a macro or fragment using itself forever
+const MAX_DEPTH: usize = 16;
+
+/// Words that start a statement instead of naming a command
+const KEYWORDS: [&str; 11] = [
+    "let", "define", "end", "include", "onfail", "fallback", "repeat", "if", "together", "pursue",
+    "at",
+];
+
+/// A reusable group of lines declared with `define name(params) ... end`
+struct Macro<'a> {
+    params: Vec<String>,
+    body: Vec<Line<'a>>,
+}
+
+/// Expands variables and macros into a flat list of commands
+struct Expander<'a> {
+    macros: BTreeMap<String, Macro<'a>>,
+    commands: Vec<Command>,
+    /// The `fallback { ... }` block, expanded once the rest of the path is done
+    /// with the variables it could see
+    fallback: Option<(Line<'a>, Vec<Line<'a>>, Variables)>,
+    /// The first `onfail fallback`, which needs a fallback section to skip to
+    skips_to_fallback: Option<Line<'a>>,
+}
+
+impl<'a> Expander<'a> {
+    fn expand(
+        &mut self,
+        lines: &[Line<'a>],
+        variables: &mut Variables,
+        depth: usize,
+    ) -> Result<(), ParseError> {
+        let mut lines = lines.iter();
+
+        while let Some(line) = lines.next() {
+            let (action, args) = (line.action(), line.args());
+
+            match action {
+                // Variable declarations don't produce a command
+                "let" => variables.declare(args).map_err(|e| line.error(e))?,
+                "define" => {
+                    let (name, params) = parse::macro_signature(args).map_err(|e| line.error(e))?;
+                    if Command::is_reserved(&name) {
+                        return Err(line.error(
+                            ArgError::invalid(
+                                "a macro name that isn't a command or keyword",
+                                &name,
+                            )
+                            .at(0),
+                        ));
+                    }
+
+                    // Everything up to the matching `end` makes up the macro
+                    let mut body = Vec::new();
+                    loop {
+                        let Some(next) = lines.next() else {
+                            return Err(
+                                line.error(ArgError::end_of_file("`end` to close the macro"))
+                            );
+                        };
+
+                        match next.action() {
+                            "end" => break,
+                            "define" => {
+                                return Err(next.error(ArgError::invalid(
+                                    "`end` before the next macro",
+                                    "define",
+                                )));
+                            }
+                            _ => body.push(next.clone()),
+                        }
+                    }
+
+                    self.macros.insert(name, Macro { params, body });
+                }
+                "include" => self.include(line, variables, depth)?,
+                "onfail" => {
+                    let failure = parse::on_fail(args).map_err(|e| line.error(e))?;
+                    if failure == Failure::Fallback && self.skips_to_fallback.is_none() {
+                        self.skips_to_fallback = Some(line.clone());
+                    }
+                    self.commands.push(Command::OnFail(failure));
+                }
+                "fallback" => {
+                    if args != ["{"] {
+                        return Err(line.error(match args.first() {
+                            Some(arg) => ArgError::invalid("`{` to open the block", arg).at(0),
+                            None => ArgError::count("`{` to open the block", 0),
+                        }));
+                    }
+                    if self.fallback.is_some() {
+                        return Err(line.error(ArgError::invalid(
+                            "only one `fallback` section in a path",
+                            action,
+                        )));
+                    }
+                    let (body, end) = block(&mut lines, line)?;
+                    lone_brace(end)?;
+                    self.fallback = Some((line.clone(), body, variables.clone()));
+                }
+                "repeat" => {
+                    // The count itself may be a variable, e.g. `repeat RINGS {`
+                    let count = variables.substitute(args.first().copied().unwrap_or_default());
+                    let (count, counter) =
+                        parse::repeat(&count, args).map_err(|e| line.error(e))?;
+                    let (body, end) = block(&mut lines, line)?;
+                    lone_brace(end)?;
+
+                    // Each pass gets its own scope so `let` inside the loop starts fresh
+                    for i in 0..count {
+                        let mut scope = variables.clone();
+                        if let Some(counter) = &counter {
+                            scope
+                                .set(counter, &i.to_string())
+                                .map_err(|e| line.error(e))?;
+                        }
+                        self.expand(&body, &mut scope, depth)?;
+                    }
+                }
+                "if" => {
+                    let args: Vec<String> =
+                        args.iter().map(|arg| variables.substitute(arg)).collect();
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    let condition = parse::condition(&args).map_err(|e| line.error(e))?;
+
+                    // Skip over the first block when the condition isn't met
+                    let (then, end) = block(&mut lines, line)?;
+                    let branch = self.commands.len();
+                    self.commands.push(Command::JumpUnless(condition, 0));
+                    self.expand(&then, &mut variables.clone(), depth)?;
+
+                    match end.args() {
+                        [] => self.land(branch),
+                        ["else", "{"] => {
+                            // Skip over the `else` block once the first block is done
+                            let (otherwise, end) = block(&mut lines, end)?;
+                            lone_brace(end)?;
+                            let skip = self.commands.len();
+                            self.commands.push(Command::Jump(0));
 
-    // Trim any whitespace and remove empty lines
-    for line in path.lines().map(str::trim).filter(|l| !l.is_empty()) {
-        if line.starts_with("//") {
-            continue; // Ignore comments
+                            self.land(branch);
+                            self.expand(&otherwise, &mut variables.clone(), depth)?;
+                            self.land(skip);
+                        }
+                        [arg, ..] => {
+                            return Err(end.error(ArgError::invalid("`else {`", arg).at(0)));
+                        }
+                    }
+                }
+                "together" => {
+                    if args != ["{"] {
+                        return Err(line.error(match args.first() {
+                            Some(arg) => ArgError::invalid("`{` to open the block", arg).at(0),
+                            None => ArgError::count("`{` to open the block", 0),
+                        }));
+                    }
+                    let (body, end) = block(&mut lines, line)?;
+                    lone_brace(end)?;
+                    self.together(&body, variables, depth)?;
+                }
+                "pursue" => {
+                    let args: Vec<String> =
+                        args.iter().map(|arg| variables.substitute(arg)).collect();
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    let (lookahead, speed) = parse::pursue(&args).map_err(|e| line.error(e))?;
+                    let (body, end) = block(&mut lines, line)?;
+                    lone_brace(end)?;
+
+                    // Expand the body on its own to check it only lays out the path
+                    let outer = mem::take(&mut self.commands);
+                    self.expand(&body, &mut variables.clone(), depth)?;
+                    let waypoints = mem::replace(&mut self.commands, outer);
+
+                    let waypoint = |command: &Command| match *command {
+                        Command::Coordinate(_, reverse) | Command::CubicBezier(.., reverse) => {
+                            Some(reverse)
+                        }
+                        _ => None,
+                    };
+                    let directions: Option<Vec<bool>> = waypoints.iter().map(waypoint).collect();
+                    let Some(directions) = directions else {
+                        return Err(line.error(ArgError::invalid(
+                            "only `Coordinate` and `Bezier` inside `pursue`",
+                            action,
+                        )));
+                    };
+                    // The whole path is driven one way, so it can't switch partway
+                    if directions.windows(2).any(|pair| pair[0] != pair[1]) {
+                        return Err(line.error(ArgError::invalid(
+                            "every waypoint in `pursue` to drive the same way",
+                            action,
+                        )));
+                    }
+
+                    self.commands
+                        .push(Command::Pursuit(lookahead, speed, waypoints.len()));
+                    self.commands.extend(waypoints);
+                }
+                _ if self.macros.contains_key(action) => self.call(line, variables, depth)?,
+                _ => {
+                    // A motion can open a block of markers, e.g. `Drive 24 {`
+                    let markers = line.opens_block();
+                    let args = if markers {
+                        &args[..args.len() - 1]
+                    } else {
+                        args
+                    };
+
+                    // Replace any variables with their values before parsing the
+                    // arguments, including the values of modifiers like `speed=SLOW`
+                    let args: Vec<String> = args
+                        .iter()
+                        .map(|arg| match arg.split_once('=') {
+                            Some((key, value)) => format!("{key}={}", variables.substitute(value)),
+                            None => variables.substitute(arg),
+                        })
+                        .collect();
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    let (args, modifiers) = parse::modifiers(&args).map_err(|e| line.error(e))?;
+
+                    // Try to convert the action + arguments into a Command enum variant
+                    let command = Command::from_str(action, args).map_err(|e| line.error(e))?;
+
+                    if let Some(modifiers) = modifiers {
+                        if !matches!(
+                            command,
+                            Command::Coordinate(..)
+                                | Command::CubicBezier(..)
+                                | Command::MoveToPose(..)
+                                | Command::SwingTo(..)
+                                | Command::ArcTo(..)
+                                | Command::DriveBy(..)
+                                | Command::TurnTo(..)
+                        ) {
+                            return Err(line.error(ArgError::invalid(
+                                "a motion before `timeout=`, `speed=` or `tol=`",
+                                action,
+                            )));
+                        }
+                        self.commands.push(Command::With(modifiers));
+                    }
+
+                    if markers {
+                        if !matches!(
+                            command,
+                            Command::DriveBy(..)
+                                | Command::Coordinate(..)
+                                | Command::CubicBezier(..)
+                        ) {
+                            return Err(line.error(ArgError::invalid(
+                                "`Drive`, `Coordinate` or `Bezier` before a block of markers",
+                                action,
+                            )));
+                        }
+
+                        // Markers go before the motion so they're known when it starts
+                        let (body, end) = block(&mut lines, line)?;
+                        lone_brace(end)?;
+                        for marker in &body {
+                            self.commands.extend(parse_marker(marker, variables)?);
+                        }
+                    }
+
+                    self.commands.push(command);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Expands a `together` block. At most one statement in the block may move
+    /// the robot, and it starts with the block. The rest run mechanisms one
+    /// after another from the start of the block, alongside the motion, with
+    /// any `Sleep`s between them waiting before the next one.
+    fn together(
+        &mut self,
+        body: &[Line<'a>],
+        variables: &Variables,
+        depth: usize,
+    ) -> Result<(), ParseError> {
+        let mut scope = variables.clone();
+        let mut movements: Option<Vec<Command>> = None;
+        let mut actions = Vec::new();
+
+        let mut lines = body.iter();
+        while let Some(line) = lines.next() {
+            // A statement is a single line, or a line along with any blocks it opens
+            let mut statement = vec![line.clone()];
+            let mut opening = line;
+            while opening.opens_block() {
+                let (block, end) = block(&mut lines, opening)?;
+                statement.extend(block);
+                statement.push(end.clone());
+                opening = end;
+            }
+
+            // Expand the statement on its own to see what it does
+            let outer = mem::take(&mut self.commands);
+            self.expand(&statement, &mut scope, depth)?;
+            let commands = mem::replace(&mut self.commands, outer);
+
+            let moves = commands.iter().any(Command::is_movement);
+            let acts = commands.iter().any(Command::is_action);
+            let invalid = |expected| line.error(ArgError::invalid(expected, line.action()));
+
+            let branches = commands.iter().any(|command| {
+                matches!(
+                    command,
+                    Command::Jump(_)
+                        | Command::JumpUnless(..)
+                        | Command::Together(..)
+                        | Command::WaitUntil(..)
+                        | Command::OnFail(_)
+                )
+            });
+
+            if branches {
+                return Err(invalid(
+                    "no `if`, `together`, `WaitUntil` or `onfail` inside `together`",
+                ));
+            } else if moves && acts {
+                return Err(invalid(
+                    "a line that either moves the robot or runs mechanisms",
+                ));
+            } else if moves {
+                if movements.is_some() {
+                    return Err(invalid("only one line that moves the robot"));
+                }
+                movements = Some(commands);
+            } else {
+                actions.extend(commands);
+            }
+        }
+
+        let movements = movements.unwrap_or_default();
+        self.commands
+            .push(Command::Together(movements.len(), actions.len()));
+        self.commands.extend(movements);
+        self.commands.extend(actions);
+
+        Ok(())
+    }
+
+    /// Points a jump at the next command to be added
+    fn land(&mut self, jump: usize) {
+        let next = self.commands.len();
+        if let Command::Jump(target) | Command::JumpUnless(_, target) = &mut self.commands[jump] {
+            *target = next;
+        }
+    }
+
+    /// Expands a macro in place, with its parameters set to the given arguments
+    fn call(
+        &mut self,
+        line: &Line<'a>,
+        variables: &Variables,
+        depth: usize,
+    ) -> Result<(), ParseError> {
+        let (name, args) = (line.action(), line.args());
+        if depth >= MAX_DEPTH {
+            return Err(line.error(ArgError::invalid("a macro that doesn't call itself", name)));
+        }
+
+        let Macro { params, body } = &self.macros[name];
+        if args.len() != params.len() {
+            return Err(line.error(ArgError::count(
+                "one argument per macro parameter",
+                args.len(),
+            )));
+        }
+
+        // Parameters act like variables that only exist inside the macro
+        let mut scope = variables.clone();
+        for (i, (param, arg)) in params.iter().zip(args).enumerate() {
+            scope
+                .set(param, &variables.substitute(arg))
+                .map_err(|e| line.error(e.at(i)))?;
+        }
+
+        let body = body.clone();
+        self.expand(&body, &mut scope, depth + 1)
+    }
+
+    /// Expands a fragment in place. Fragments share variables and macros with
+    /// the path including them, so a fragment can hold macros for other paths.
+    fn include(
+        &mut self,
+        line: &Line<'a>,
+        variables: &mut Variables,
+        depth: usize,
+    ) -> Result<(), ParseError> {
+        let name = parse::fragment_name(line.args()).map_err(|e| line.error(e))?;
+        let Some((name, fragment)) = fragments::find(&name) else {
+            return Err(line.error(ArgError::invalid("the name of a fragment", &name).at(0)));
+        };
+        if depth >= MAX_DEPTH {
+            return Err(
+                line.error(ArgError::invalid("a fragment that doesn't include itself", name).at(0))
+            );
+        }
+
+        self.expand(&parse::lines(fragment), variables, depth + 1)
+            .map_err(|e| e.in_fragment(name))
+    }
+}
+
+/// Collects the lines of the `{ ... }` block started by `opening`, leaving
+/// `lines` just after its closing `}`. The closing line is returned alongside
+/// the block since it may go on to open another, like `} else {`.
+fn block<'a, 'b>(
+    lines: &mut slice::Iter<'b, Line<'a>>,
+    opening: &Line<'a>,
+) -> Result<(Vec<Line<'a>>, &'b Line<'a>), ParseError> {
+    let mut body = Vec::new();
+    let mut nested = 0;
+
+    for line in lines.by_ref() {
+        if line.action() == "}" {
+            if nested == 0 {
+                return Ok((body, line));
+            }
+            // `} else {` closes one nested block but opens another
+            if !line.opens_block() {
+                nested -= 1;
+            }
+        } else if line.opens_block() {
+            nested += 1;
         }
+        body.push(line.clone());
+    }
+
+    Err(opening.error(ArgError::end_of_file("`}` to close the block")))
+}
+
+/// Parses a marker line like `at 40% Intake on` into the marker and its action
+fn parse_marker(line: &Line, variables: &Variables) -> Result<[Command; 2], ParseError> {
+    if line.action() != "at" {
+        return Err(line.error(ArgError::invalid("`at` to start a marker", line.action())));
+    }
+
+    let args: Vec<String> = line
+        .args()
+        .iter()
+        .map(|arg| variables.substitute(arg))
+        .collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let [progress, action, rest @ ..] = args.as_slice() else {
+        return Err(line.error(ArgError::count(
+            "a point along the motion and an action",
+            args.len(),
+        )));
+    };
+
+    let progress = parse::progress(progress).map_err(|e| line.error(e.at(0)))?;
+    let command = Command::from_str(action, rest).map_err(|mut e| {
+        // Point at the action's own arguments, which start after the progress
+        e.arg = Some(e.arg.map_or(1, |arg| arg + 2));
+        line.error(e)
+    })?;
+    if !command.is_action() {
+        return Err(
+            line.error(ArgError::invalid("an intake, lady brown or clamp command", action).at(1))
+        );
+    }
+
+    Ok([Command::Marker(progress), command])
+}
+
+/// Makes sure a block was closed by a `}` with nothing after it
+fn lone_brace(end: &Line) -> Result<(), ParseError> {
+    match end.args().first() {
+        Some(arg) => Err(end.error(ArgError::invalid("nothing after `}`", arg).at(0))),
+        None => Ok(()),
+    }
+}
+
+/// Converts a path string into a list of commands
+pub fn path_to_commands(path: &str) -> Result<Vec<Command>, ParseError> {
+    let lines = parse::lines(path);
 
-        let tokens: Vec<&str> = line.split_whitespace().collect();
-        let (action, args) = tokens.split_first().ok_or("Empty command")?;
+    let mut expander = Expander {
+        macros: BTreeMap::new(),
+        commands: Vec::new(),
+        fallback: None,
+        skips_to_fallback: None,
+    };
+    expander.expand(&lines, &mut Variables::default(), 0)?;
 
-        // Try to convert the action + arguments into a Command enum variant
-        commands.push(Command::from_str(action, args)?);
+    // The fallback section goes after the end of the path, so it only runs
+    // when a failure skips to it
+    if let Some((_, body, mut variables)) = expander.fallback.take() {
+        expander.commands.push(Command::Fallback);
+        expander.expand(&body, &mut variables, 0)?;
+        if let Some((nested, ..)) = expander.fallback {
+            return Err(nested.error(ArgError::invalid(
+                "no `fallback` inside `fallback`",
+                nested.action(),
+            )));
+        }
+    } else if let Some(line) = expander.skips_to_fallback {
+        return Err(line.error(
+            ArgError::invalid("a `fallback { ... }` section in the path", "fallback").at(0),
+        ));
     }
 
-    Ok(commands)
+    Ok(expander.commands)
 }
 
 /// Converts movement-related commands into explicit coordinate-based commands.
 /// This function ensures that movement commands (such as `DriveBy` and `Pose`)
 /// are converted into explicit `Coordinate` commands for path processing.
-pub fn command_to_coords(path: &[Command]) -> Vec<Command> {
+/// Swings and arcs need the robot's `track_width` to know where it pivots.
+pub fn command_to_coords(path: &[Command], track_width: f64) -> Vec<Command> {
     let mut new_path = Vec::new();
 
     // Initialize the starting pose based on the first command
     let (mut pose, path) = match path.first() {
         // Start at the given coordinate
-        Some(Command::Coordinate(coord)) => (Pose::new(*coord, 0.0), &path[1..]),
+        Some(Command::Coordinate(coord, _)) => (Pose::new(*coord, 0.0), &path[1..]),
         // Start at the first point of a cubic Bézier curve
         Some(Command::CubicBezier(p0, ..)) => (Pose::new(*p0, 0.0), path),
         // Explicitly defined start position and angle
@@ -106,37 +835,109 @@ pub fn command_to_coords(path: &[Command]) -> Vec<Command> {
     };
 
     // Ensure the first command in the converted path is a coordinate
-    new_path.push(Command::Coordinate(pose.position));
+    new_path.push(Command::Coordinate(pose.position, false));
 
     // Process each command and update the pose accordingly
     for &command in path {
         match command {
-            Command::Coordinate(coord) => {
-                // If the command specifies a direct coordinate, update position
+            Command::Coordinate(coord, reverse) => {
+                // If the command specifies a direct coordinate, update position.
+                // The robot ends up facing the way it drove, or away from it in reverse.
+                if let Some(heading) = travel_heading(pose.position, coord, reverse) {
+                    pose.heading = heading;
+                }
                 pose.position = coord;
                 new_path.push(command);
             }
-            Command::DriveBy(distance) => {
+            Command::CubicBezier(_, _, p2, p3, reverse) => {
+                // The robot ends up facing along the end of the curve
+                if let Some(heading) = travel_heading(p2, p3, reverse) {
+                    pose.heading = heading;
+                }
+                pose.position = p3;
+                new_path.push(command);
+            }
+            Command::MoveToPose(coord, angle, _, reverse) => {
+                // Arrive at the point facing the final heading
+                pose = Pose::new(coord, angle);
+                new_path.push(Command::Coordinate(pose.position, reverse));
+            }
+            Command::DriveBy(distance, _) => {
                 // Move forward by `distance` along the current heading
                 pose.position += Vec2::from_polar(distance, pose.heading.to_radians());
-                new_path.push(Command::Coordinate(pose.position)); // Store the new coordinate
+                // Store the new coordinate
+                new_path.push(Command::Coordinate(pose.position, distance < 0.0));
             }
             Command::TurnBy(angle) => {
                 // Turn relative to the current heading
                 pose.heading = (pose.heading + angle) % 360.0;
             }
-            Command::TurnTo(angle) => {
+            Command::TurnTo(angle, _) => {
                 // Set the absolute heading
                 pose.heading = angle % 360.0;
             }
+            Command::SwingTo(angle, side) => {
+                // Pivot around the wheels on the held side
+                let offset = match side {
+                    Side::Left => -track_width / 2.0,
+                    Side::Right => track_width / 2.0,
+                };
+                arc_to_coords(&mut pose, offset, angle, &mut new_path);
+            }
+            Command::ArcTo(angle, radius) => {
+                // Curve around a point on the side the robot is turning towards
+                let turn = shortest_turn(pose.heading, angle);
+                let offset = if turn < 0.0 { -radius } else { radius };
+                arc_to_coords(&mut pose, offset, angle, &mut new_path);
+            }
             Command::Pose(coord, angle) => {
                 // Directly set the position and angle
                 pose = Pose::new(coord, angle);
-                new_path.push(Command::Coordinate(pose.position)); // Store the new coordinate
+                // Store the new coordinate
+                new_path.push(Command::Coordinate(pose.position, false));
             }
+            // Only the path itself is drawn, not what happens if it fails
+            Command::Fallback => break,
             _ => new_path.push(command), // Keep other commands unchanged
         }
     }
 
     new_path
 }
+
+/// Heading (degrees) the robot faces after driving straight from one point to
+/// another, or `None` if it doesn't move
+fn travel_heading(from: Vec2<f64>, to: Vec2<f64>, reverse: bool) -> Option<f64> {
+    let travel = Vec2::new(to.x() - from.x(), to.y() - from.y());
+    if travel.x() == 0.0 && travel.y() == 0.0 {
+        return None;
+    }
+
+    let heading = travel.angle().to_degrees();
+    Some(if reverse { heading + 180.0 } else { heading })
+}
+
+/// Signed angle (degrees) of the shortest turn from one heading to another
+fn shortest_turn(from: f64, to: f64) -> f64 {
+    ((to - from) % 360.0 + 540.0) % 360.0 - 180.0
+}
+
+/// Moves the pose around a circle to face `angle`, adding coordinates along
+/// the way so the curve is drawn. The circle's center is `offset` inches to
+/// the robot's right, or to its left if negative.
+fn arc_to_coords(pose: &mut Pose, offset: f64, angle: f64, path: &mut Vec<Command>) {
+    let mut center = pose.position;
+    center += Vec2::from_polar(offset, (pose.heading + 90.0).to_radians());
+
+    // A point about every 10 degrees of the turn
+    let turn = shortest_turn(pose.heading, angle);
+    let steps = ((turn.abs() / 10.0) as usize).max(1);
+    for i in 1..=steps {
+        let heading = pose.heading + turn * i as f64 / steps as f64;
+        pose.position = center;
+        pose.position += Vec2::from_polar(offset, (heading - 90.0).to_radians());
+        path.push(Command::Coordinate(pose.position, offset * turn < 0.0));
+    }
+
+    pose.heading = angle % 360.0;
+}
@@ -0,0 +1,99 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::fmt;
+
+/// Describes why a line of a `.botpath` file could not be turned into a command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// The included fragment the error is in, `None` if it's in the path itself
+    pub fragment: Option<&'static str>,
+    /// Line of the path file the error was found on (starting at 1)
+    pub line: usize,
+    /// Column of the offending token within the line (starting at 1)
+    pub column: usize,
+    /// The token that could not be parsed
+    pub token: String,
+    /// The command that was being parsed
+    pub command: String,
+    /// What the parser was expecting to find
+    pub expected: &'static str,
+    /// What was actually found instead
+    pub found: String,
+}
+
+impl ParseError {
+    /// Marks the error as coming from an included fragment, unless it already
+    /// came from a fragment included deeper down
+    pub fn in_fragment(mut self, name: &'static str) -> Self {
+        self.fragment.get_or_insert(name);
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(fragment) = &self.fragment {
+            write!(f, "in \"{}\", ", fragment)?;
+        }
+        write!(
+            f,
+            "line {}, column {}: {}: expected {}, found {}",
+            self.line, self.column, self.command, self.expected, self.found
+        )
+    }
+}
+
+/// An error for a single command's arguments. It does not know where in the
+/// file it came from, `path_to_commands` fills that in when building a `ParseError`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArgError {
+    /// Index of the offending argument, `None` if the argument list as a whole is wrong
+    pub arg: Option<usize>,
+    /// What the parser was expecting to find
+    pub expected: &'static str,
+    /// What was actually found instead
+    pub found: String,
+}
+
+impl ArgError {
+    /// The wrong number of arguments were given to a command
+    pub fn count(expected: &'static str, found: usize) -> Self {
+        Self {
+            arg: None,
+            expected,
+            found: match found {
+                0 => "no arguments".to_string(),
+                1 => "1 argument".to_string(),
+                n => format!("{n} arguments"),
+            },
+        }
+    }
+
+    /// A token could not be parsed as the expected type
+    pub fn invalid(expected: &'static str, token: &str) -> Self {
+        Self {
+            arg: None,
+            expected,
+            found: format!("`{token}`"),
+        }
+    }
+
+    /// The file ended before something the parser was waiting for, like the
+    /// end of a block
+    pub fn end_of_file(expected: &'static str) -> Self {
+        Self {
+            arg: None,
+            expected,
+            found: "the end of the file".to_string(),
+        }
+    }
+
+    /// Points the error at the argument with the given index, unless it
+    /// already points at one
+    pub fn at(mut self, index: usize) -> Self {
+        self.arg.get_or_insert(index);
+        self
+    }
+}
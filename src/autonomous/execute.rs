@@ -1,72 +1,1098 @@
-use core::time::Duration;
+use alloc::{vec, vec::Vec};
+use core::{
+    cell::Cell,
+    f64::consts::PI,
+    future::{Future, poll_fn},
+    mem,
+    pin::pin,
+    task::Poll,
+    time::Duration,
+};
 
-use evian::{
-    control::loops::{AngularPid, Pid},
-    math::IntoAngle,
-    motion::{Basic, Seeking},
-    prelude::*,
+use super::{
+    basic::Basic,
+    command::{Command, Condition, Failure, Modifiers, Profile, Side},
+    report::{MotionResult, Outcome, Report},
+};
+use crate::{
+    config::{DRIVE_RPM, MAX_VOLTAGE, TRACK_WIDTH, WHEEL_DIAMETER},
+    hardware::{BrakeMode, Hardware, MotorDevice, PneumaticDevice},
+    motion::{
+        Follower, Reversed,
+        boomerang::{Boomerang, BoomerangGains},
+        curve::CubicBezier,
+        pid::{Pid, Tolerances},
+        profile::{MotionProfile, ProfileConstraints, ProfileGains},
+        pursuit::PurePursuit,
+        ramsete::{Ramsete, RamseteGains},
+        trajectory::{Constraints, Trajectory},
+        wrap_angle,
+    },
+    platform::{Float, Instant, sleep},
+    pose::Vec2,
+    robot::Robot,
+    subsystems::{
+        drivetrain::{self, DriveMotors, Drivetrain},
+        intake::Intake,
+        lady_brown::LadyBrown,
+    },
 };
-use vexide::prelude::{Motor, sleep};
 
-use super::command::Command;
-use crate::{Robot /*, DRIVE_RPM, GEARING, TRACK_WIDTH, WHEEL_DIAMETER*/};
+/// How close a motion has to get to its target to finish, unless it was given
+/// its own `tol=` (inches, or degrees for turns). Basic turns settle with
+/// `TOLERANCES` instead, like drives, which is in radians for them.
+const TOLERANCE: f64 = 0.5;
 
 pub const TOLERANCES: Tolerances = Tolerances::new()
     .duration(Duration::from_millis(250))
-    .error(0.5)
+    .error(TOLERANCE)
     .velocity(50.0);
 
+/// Drive motor speed (rpm) below which the drivetrain counts as stopped
+const SETTLED_RPM: f64 = 5.0;
+
+/// How long the wheels have to stay still before a motion counts as stalled
+const STALL_TIME: Duration = Duration::from_millis(500);
+
+/// How far (inches) either side has to roll between checks to count as moving
+const STALL_DISTANCE: f64 = 0.1;
+
+/// Top speed of the drive wheels (inches/second)
+const DRIVE_SPEED: f64 = DRIVE_RPM / 60.0 * WHEEL_DIAMETER * PI;
+
+/// Limits on how fast curves are driven, kept under top speed so the
+/// controller has room to correct
+pub const CONSTRAINTS: Constraints = Constraints {
+    max_velocity: 0.8 * DRIVE_SPEED,
+    max_acceleration: 100.0,
+    max_centripetal_acceleration: 80.0,
+    track_width: TRACK_WIDTH,
+};
+
+/// The usual RAMSETE gains of b = 2.0 1/m² and zeta = 0.7, with b converted to inches
+pub const RAMSETE_GAINS: RamseteGains = RamseteGains {
+    b: 2.0 / (39.37 * 39.37),
+    zeta: 0.7,
+};
+
+pub const BOOMERANG_GAINS: BoomerangGains = BoomerangGains {
+    linear: 4.0,
+    angular: 6.0,
+    lead: 0.6,
+};
+
+/// Limits for profiled straight drives (inches). The jerk limit is only used
+/// for S-curves.
+pub const DRIVE_PROFILE: ProfileConstraints = ProfileConstraints {
+    max_velocity: 0.8 * DRIVE_SPEED,
+    max_acceleration: 120.0,
+    max_jerk: Some(600.0),
+};
+
+/// Limits for profiled turns (radians). Turning in place, the wheels move
+/// `TRACK_WIDTH / 2` inches for every radian.
+pub const TURN_PROFILE: ProfileConstraints = ProfileConstraints {
+    max_velocity: 0.6 * DRIVE_SPEED / (TRACK_WIDTH / 2.0),
+    max_acceleration: 20.0,
+    max_jerk: Some(120.0),
+};
+
+pub const DRIVE_GAINS: ProfileGains = ProfileGains {
+    ks: 0.5,
+    kv: MAX_VOLTAGE / DRIVE_SPEED,
+    ka: 0.02,
+    kp: 0.8,
+};
+
+pub const TURN_GAINS: ProfileGains = ProfileGains {
+    ks: 0.5,
+    kv: MAX_VOLTAGE / (DRIVE_SPEED / (TRACK_WIDTH / 2.0)),
+    ka: 0.1,
+    kp: 10.0,
+};
+
+/// How far ahead to aim when backing into a point (inches)
+const REVERSE_LOOKAHEAD: f64 = 8.0;
+
+/// Wheel speed (inches/second) added for every inch a side has fallen behind
+/// where it should be on an arc
+const ARC_KP: f64 = 40.0;
+
+/// Voltage per radian of drift to steer back on course during profiled drives
+const HEADING_KP: f64 = 8.0;
+
 pub const LINEAR_CONTROLLER: Pid = Pid::new(1.25, 0.0, 0.0, None);
-pub const ANGULAR_CONTROLLER: AngularPid =
-    AngularPid::new(30.0, 1.75, 2.0, Some(Angle::from_degrees(25.0)));
-
-pub async fn execute_command(robot: &mut Robot, command: Command, basic: &mut Basic<Pid, AngularPid>) {
-    let mut seeking = Seeking {
-        linear_controller: LINEAR_CONTROLLER,
-        angular_controller: ANGULAR_CONTROLLER,
-        tolerances: TOLERANCES,
-        timeout: Some(Duration::from_millis(2000)),
+pub const ANGULAR_CONTROLLER: Pid = Pid::new(30.0, 1.75, 2.0, Some(25.0_f64.to_radians()));
+
+/// How fast the robot is allowed to move, kept for the rest of a routine once
+/// set by `Speed` or `TurnSpeed`
+#[derive(Clone, Copy, Debug)]
+pub struct MotionSettings {
+    /// Fraction of top speed for drives, point and curve moves
+    pub speed: f64,
+
+    /// Fraction of top speed for turns, swings and arcs
+    pub turn_speed: f64,
+}
+
+impl Default for MotionSettings {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            turn_speed: 1.0,
+        }
+    }
+}
+
+impl MotionSettings {
+    /// The limits for a single motion, where its own `speed=` replaces both
+    fn with(self, modifiers: Modifiers) -> Self {
+        Self {
+            speed: modifiers.speed.unwrap_or(self.speed),
+            turn_speed: modifiers.speed.unwrap_or(self.turn_speed),
+        }
+    }
+
+    /// Caps a top driving speed (inches/second) at the speed limit
+    fn max_velocity(self, max_velocity: f64) -> f64 {
+        max_velocity.min(self.speed * DRIVE_SPEED)
+    }
+
+    /// Caps a top turning speed (radians/second) at the turn speed limit.
+    /// Turning in place, the wheels move `TRACK_WIDTH / 2` inches per radian.
+    fn max_turn_velocity(self, max_velocity: f64) -> f64 {
+        max_velocity.min(self.turn_speed * DRIVE_SPEED / (TRACK_WIDTH / 2.0))
+    }
+
+    /// Controllers for a `Basic` motion, with their outputs
+    /// limited and the motion's timeout and tolerance applied
+    fn basic(self, modifiers: Modifiers) -> Basic {
+        let mut basic = Basic {
+            linear_controller: LINEAR_CONTROLLER,
+            angular_controller: ANGULAR_CONTROLLER,
+            linear_tolerances: TOLERANCES,
+            angular_tolerances: TOLERANCES,
+            timeout: Duration::from_millis(modifiers.timeout.unwrap_or(2000)),
+        };
+
+        basic
+            .linear_controller
+            .set_output_limit(Some(MAX_VOLTAGE * self.speed));
+        basic
+            .angular_controller
+            .set_output_limit(Some(MAX_VOLTAGE * self.turn_speed));
+
+        if let Some(tolerance) = modifiers.tolerance {
+            // Turns settle on the heading error, which is in radians
+            basic.linear_tolerances = TOLERANCES.error(tolerance);
+            basic.angular_tolerances = TOLERANCES.error(tolerance.to_radians());
+        }
+
+        basic
+    }
+}
+
+/// Runs a path from start to finish, following any branches along the way.
+/// How every motion went is added to `report` as it finishes, so it's kept
+/// even if the path is cut off partway through.
+pub async fn execute_path(
+    robot: &mut Robot<impl Hardware>,
+    path: &[Command],
+    settings: &mut MotionSettings,
+    report: &mut Report,
+) {
+    // Modifiers wait here until the motion they belong to runs
+    let mut modifiers = Modifiers::default();
+    let mut on_fail = Failure::Continue;
+    let mut in_fallback = false;
+
+    // The first command is the starting position, which is set before the path runs
+    let mut index = 1;
+    while let Some(&command) = path.get(index) {
+        index += 1;
+        let finished = report.results.len();
+
+        match command {
+            Command::With(next) => modifiers = next,
+            Command::OnFail(failure) => on_fail = failure,
+            // Running into the fallback section means the path itself is done
+            Command::Fallback => break,
+            Command::Jump(target) => index = target,
+            Command::JumpUnless(condition, target) => {
+                if !check_condition(robot, condition, report) {
+                    index = target;
+                }
+            }
+            Command::WaitUntil(condition, timeout) => {
+                let timeout = Duration::from_millis(timeout);
+                let waiting = Instant::now();
+                while !check_condition(robot, condition, report) && waiting.elapsed() < timeout {
+                    sleep(Duration::from_millis(10)).await;
+                }
+            }
+            Command::Pursuit(lookahead, speed, count) => {
+                let waypoints = &path[index..index + count];
+                let speed = speed.min(settings.speed);
+                let Robot {
+                    drivetrain,
+                    drive_motors,
+                    ..
+                } = robot;
+                execute_pursuit(
+                    drivetrain,
+                    drive_motors,
+                    lookahead,
+                    speed,
+                    waypoints,
+                    report,
+                )
+                .await;
+                index += count;
+            }
+            Command::Together(movements, actions) => {
+                let movements_end = index + movements;
+                let actions_end = movements_end + actions;
+                execute_together(
+                    robot,
+                    &path[index..movements_end],
+                    &path[movements_end..actions_end],
+                    settings,
+                    report,
+                )
+                .await;
+                index = actions_end;
+            }
+            Command::Marker(_) => {
+                // Each marker is paired with its action, and the motion they
+                // belong to comes right after the last pair
+                let markers_start = index - 1;
+                let mut motion_index = markers_start;
+                while let Some(Command::Marker(_)) = path.get(motion_index) {
+                    motion_index += 2;
+                }
+                let Some(&motion) = path.get(motion_index) else {
+                    break;
+                };
+
+                let markers = &path[markers_start..motion_index];
+                let modifiers = mem::take(&mut modifiers);
+                execute_with_markers(robot, markers, motion, modifiers, settings, report).await;
+                index = motion_index + 1;
+            }
+            _ if command.is_movement() => {
+                let modifiers = mem::take(&mut modifiers);
+                let Robot {
+                    drivetrain,
+                    drive_motors,
+                    ..
+                } = robot;
+                execute_motion(
+                    drivetrain,
+                    drive_motors,
+                    command,
+                    modifiers,
+                    settings,
+                    report,
+                )
+                .await;
+            }
+            _ => {
+                let Robot {
+                    intake,
+                    lady_brown,
+                    clamp,
+                    ..
+                } = robot;
+                execute_action(intake, lady_brown, clamp, command).await;
+            }
+        }
+
+        // Anything that just ran may have failed, which `onfail` decides what to do about
+        if report.results[finished..].iter().any(MotionResult::failed) {
+            match on_fail {
+                Failure::Continue => (),
+                // The fallback section starts over with failures ignored, and
+                // failing there too gives up on the path
+                Failure::Fallback if !in_fallback => {
+                    let fallback = path
+                        .iter()
+                        .position(|command| matches!(command, Command::Fallback));
+                    if let Some(fallback) = fallback {
+                        index = fallback + 1;
+                        in_fallback = true;
+                        on_fail = Failure::Continue;
+                    }
+                }
+                Failure::Fallback | Failure::Abort => {
+                    report.aborted = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Checks the robot's current state against a path's condition
+fn check_condition(robot: &Robot<impl Hardware>, condition: Condition, report: &Report) -> bool {
+    let alliance = robot.settings.borrow().curr_color;
+
+    match condition {
+        Condition::Clamped => robot.clamp.0.is_extended().unwrap_or(false),
+        Condition::AllianceRing => robot.intake.ring() == Some(alliance),
+        Condition::OpponentRing => robot.intake.ring() == Some(alliance.opposite()),
+        Condition::AnyRing => robot.intake.ring().is_some(),
+        Condition::NoRing => robot.intake.ring().is_none(),
+        Condition::Settled => drive_settled(&robot.drive_motors),
+        Condition::LadyBrownAbove(angle) => robot.lady_brown.angle() > angle,
+        Condition::LadyBrownBelow(angle) => robot.lady_brown.angle() < angle,
+        Condition::ElapsedOver(time) => report.start.elapsed() > Duration::from_millis(time),
+        Condition::ElapsedUnder(time) => report.start.elapsed() < Duration::from_millis(time),
+        Condition::Failed => report.last_failed(),
+    }
+}
+
+/// Runs a single command outside of a path. If it moves the robot, how it went
+/// is added to `report`, timed from the start of the run the report covers.
+pub async fn execute_command(
+    robot: &mut Robot<impl Hardware>,
+    command: Command,
+    settings: &mut MotionSettings,
+    report: &mut Report,
+) {
+    let Robot {
+        drivetrain,
+        intake,
+        lady_brown,
+        clamp,
+        drive_motors,
+        ..
+    } = robot;
+
+    if command.is_action() {
+        execute_action(intake, lady_brown, clamp, command).await;
+    } else {
+        let modifiers = Modifiers::default();
+        execute_motion(
+            drivetrain,
+            drive_motors,
+            command,
+            modifiers,
+            settings,
+            report,
+        )
+        .await;
+    }
+}
+
+/// Runs a `together` block, where the robot drives through one set of commands
+/// while running its mechanisms through the other. Finishes once both are done.
+async fn execute_together(
+    robot: &mut Robot<impl Hardware>,
+    movements: &[Command],
+    actions: &[Command],
+    settings: &mut MotionSettings,
+    report: &mut Report,
+) {
+    // The two sides use different parts of the robot, so they can run at once
+    let Robot {
+        drivetrain,
+        intake,
+        lady_brown,
+        clamp,
+        drive_motors,
+        ..
+    } = robot;
+
+    join(
+        async {
+            let mut modifiers = Modifiers::default();
+            let mut index = 0;
+            while let Some(&command) = movements.get(index) {
+                index += 1;
+
+                match command {
+                    Command::With(next) => modifiers = next,
+                    Command::Pursuit(lookahead, speed, count) => {
+                        let waypoints = &movements[index..index + count];
+                        let speed = speed.min(settings.speed);
+                        execute_pursuit(
+                            drivetrain,
+                            drive_motors,
+                            lookahead,
+                            speed,
+                            waypoints,
+                            report,
+                        )
+                        .await;
+                        index += count;
+                    }
+                    _ => {
+                        let modifiers = mem::take(&mut modifiers);
+                        execute_motion(
+                            drivetrain,
+                            drive_motors,
+                            command,
+                            modifiers,
+                            settings,
+                            report,
+                        )
+                        .await;
+                    }
+                }
+            }
+        },
+        async {
+            for &command in actions {
+                execute_action(intake, lady_brown, clamp, command).await;
+            }
+        },
+    )
+    .await;
+}
+
+/// Runs a motion, firing each marker's action once the robot has traveled far
+/// enough along it. Any markers not reached by the end of the motion fire then.
+async fn execute_with_markers(
+    robot: &mut Robot<impl Hardware>,
+    markers: &[Command],
+    motion: Command,
+    modifiers: Modifiers,
+    settings: &mut MotionSettings,
+    report: &mut Report,
+) {
+    let length = match motion {
+        Command::DriveBy(distance, _) => distance.abs(),
+        Command::Coordinate(target, _) => {
+            let position = robot.drivetrain.tracking.position();
+            (target.x() - position.x()).hypot(target.y() - position.y())
+        }
+        Command::CubicBezier(p0, p1, p2, p3, _) => CubicBezier::new(p0, p1, p2, p3).length(),
+        _ => 0.0,
     };
 
-    let dt = &mut robot.drivetrain;
+    let mut pending: Vec<(f64, Command)> = markers
+        .chunks_exact(2)
+        .filter_map(|pair| match *pair {
+            [Command::Marker(progress), action] => Some((progress.distance(length), action)),
+            _ => None,
+        })
+        .collect();
+    pending.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // The motion holds the drivetrain, so progress is read from the motors instead
+    let Robot {
+        drivetrain,
+        intake,
+        lady_brown,
+        clamp,
+        drive_motors,
+        ..
+    } = robot;
+    let start = drive_travel(drive_motors);
+    let done = Cell::new(false);
+
+    join(
+        async {
+            execute_motion(
+                drivetrain,
+                drive_motors,
+                motion,
+                modifiers,
+                settings,
+                report,
+            )
+            .await;
+            done.set(true);
+        },
+        async {
+            for (distance, action) in pending {
+                while !done.get() && (drive_travel(drive_motors) - start).abs() < distance {
+                    sleep(Duration::from_millis(10)).await;
+                }
+                execute_action(intake, lady_brown, clamp, action).await;
+            }
+        },
+    )
+    .await;
+}
+
+/// Average distance the drive wheels have rolled (inches), read straight from
+/// the motors. Turning in place moves the two sides in opposite directions, so
+/// only forward and backward travel counts.
+fn drive_travel(drive_motors: &DriveMotors<impl MotorDevice>) -> f64 {
+    let [left, right] = side_travel(drive_motors);
+    (left + right) / 2.0
+}
+
+/// Distance each side's wheels have rolled (inches), or zero for a side
+/// none of whose motors can be read
+fn side_travel(drive_motors: &DriveMotors<impl MotorDevice>) -> [f64; 2] {
+    drivetrain::side_travel(drive_motors).map(Option::unwrap_or_default)
+}
+
+/// Checks if every drive motor has all but stopped turning
+fn drive_settled(drive_motors: &DriveMotors<impl MotorDevice>) -> bool {
+    drive_motors.iter().all(|side| {
+        side.borrow()
+            .iter()
+            .all(|motor| motor.velocity().is_ok_and(|rpm| rpm.abs() < SETTLED_RPM))
+    })
+}
+
+/// Polls two futures at the same time until both have finished
+async fn join(a: impl Future<Output = ()>, b: impl Future<Output = ()>) {
+    let (mut a, mut b) = (pin!(a), pin!(b));
+    let (mut a_done, mut b_done) = (false, false);
+
+    poll_fn(|cx| {
+        if !a_done {
+            a_done = a.as_mut().poll(cx).is_ready();
+        }
+        if !b_done {
+            b_done = b.as_mut().poll(cx).is_ready();
+        }
+
+        if a_done && b_done {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+}
+
+/// Where a motion is trying to get the robot
+#[derive(Clone, Copy)]
+enum Target {
+    Position(Vec2<f64>),
+    /// Heading in the tracking frame (radians)
+    Heading(f64),
+    /// Forward travel reading the robot should end up at (inches)
+    Travel(f64),
+}
+
+impl Target {
+    /// The target of a command, or `None` if it doesn't move the robot anywhere
+    fn of(dt: &Drivetrain<impl MotorDevice>, command: Command) -> Option<Self> {
+        match command {
+            Command::Coordinate(point, _)
+            | Command::CubicBezier(.., point, _)
+            | Command::MoveToPose(point, ..) => Some(Target::Position(point)),
+            Command::DriveBy(distance, _) => {
+                Some(Target::Travel(dt.tracking.forward_travel() + distance))
+            }
+            Command::TurnTo(angle, _) | Command::SwingTo(angle, _) | Command::ArcTo(angle, _) => {
+                Some(Target::Heading((-angle).to_radians()))
+            }
+            _ => None,
+        }
+    }
+
+    /// How far off the robot is from the target (inches, or degrees for headings)
+    fn error(self, dt: &Drivetrain<impl MotorDevice>) -> f64 {
+        match self {
+            Target::Position(point) => {
+                let position = dt.tracking.position();
+                (point.x() - position.x()).hypot(point.y() - position.y())
+            }
+            Target::Heading(heading) => wrap_angle(heading - dt.tracking.heading())
+                .abs()
+                .to_degrees(),
+            Target::Travel(travel) => (travel - dt.tracking.forward_travel()).abs(),
+        }
+    }
+}
+
+/// Runs a movement command, adding how it went to the report if it has a
+/// target to reach
+async fn execute_motion<M: MotorDevice>(
+    dt: &mut Drivetrain<M>,
+    drive_motors: &DriveMotors<M>,
+    command: Command,
+    modifiers: Modifiers,
+    settings: &mut MotionSettings,
+    report: &mut Report,
+) {
+    let Some(target) = Target::of(dt, command) else {
+        execute_movement(dt, drive_motors, command, modifiers, settings).await;
+        return;
+    };
+
+    let started = report.start.elapsed();
+    let start = Instant::now();
+    let still_for = watch_wheels(
+        drive_motors,
+        execute_movement(dt, drive_motors, command, modifiers, settings),
+    )
+    .await;
+    let duration = start.elapsed();
+
+    let tolerance = modifiers.tolerance.unwrap_or(match command {
+        Command::TurnTo(_, None) => TOLERANCE.to_degrees(),
+        _ => TOLERANCE,
+    });
+    let (outcome, error) = judge(dt, drive_motors, target, tolerance, still_for);
+    report.record(MotionResult {
+        command,
+        outcome,
+        error,
+        started,
+        duration,
+    });
+
+    // Basic drives and turns let the robot come to rest before the next motion
+    if matches!(
+        command,
+        Command::DriveBy(_, None) | Command::TurnTo(_, None)
+    ) {
+        sleep(Duration::from_millis(250)).await;
+    }
+}
+
+/// Runs a motion while watching the drive wheels. Returns how long the wheels
+/// had been still for when it finished.
+async fn watch_wheels(
+    drive_motors: &DriveMotors<impl MotorDevice>,
+    motion: impl Future<Output = ()>,
+) -> Duration {
+    let done = Cell::new(false);
+    let last_moved = Cell::new(Instant::now());
+
+    join(
+        async {
+            motion.await;
+            done.set(true);
+        },
+        async {
+            let mut last = side_travel(drive_motors);
+            while !done.get() {
+                sleep(Duration::from_millis(100)).await;
+                let travel = side_travel(drive_motors);
+                if travel
+                    .iter()
+                    .zip(last)
+                    .any(|(now, before)| (now - before).abs() > STALL_DISTANCE)
+                {
+                    last_moved.set(Instant::now());
+                }
+                last = travel;
+            }
+        },
+    )
+    .await;
+
+    last_moved.get().elapsed()
+}
+
+/// Decides how a finished motion went from where the robot ended up, along
+/// with how far off the target it is. `tolerance` is the one the motion
+/// finishes within, so ending inside it counts however the motion stopped.
+fn judge(
+    dt: &Drivetrain<impl MotorDevice>,
+    drive_motors: &DriveMotors<impl MotorDevice>,
+    target: Target,
+    tolerance: f64,
+    still_for: Duration,
+) -> (Outcome, f64) {
+    let error = target.error(dt);
+    let connected = drive_motors
+        .iter()
+        .all(|side| side.borrow().iter().all(|motor| motor.position().is_ok()));
+
+    let outcome = if !connected {
+        Outcome::Error
+    } else if error <= tolerance {
+        Outcome::Settled
+    } else if still_for >= STALL_TIME {
+        Outcome::Stalled
+    } else {
+        Outcome::TimedOut
+    };
+
+    (outcome, error)
+}
+
+/// Runs a command that moves the drivetrain or changes how it moves, with any
+/// modifiers written after it
+async fn execute_movement<M: MotorDevice>(
+    dt: &mut Drivetrain<M>,
+    drive_motors: &DriveMotors<M>,
+    command: Command,
+    modifiers: Modifiers,
+    settings: &mut MotionSettings,
+) {
+    // The routine's speed limits, unless the motion was given its own
+    let limits = settings.with(modifiers);
+    let timeout = modifiers.timeout.map(Duration::from_millis);
+    let constraints = Constraints {
+        max_velocity: limits.max_velocity(CONSTRAINTS.max_velocity),
+        ..CONSTRAINTS
+    };
 
     match command {
-        Command::Coordinate(coord) => {
-            _ = seeking.move_to_point(dt, coord).await;
+        Command::Coordinate(coord, false) => {
+            let mut basic = limits.basic(modifiers);
+            basic.move_to_point(dt, coord).await;
+        }
+        Command::Coordinate(coord, true) => {
+            // Back straight into the point, slowing down to stop on it
+            let waypoints = vec![dt.tracking.position(), coord];
+            let tolerance = modifiers.tolerance.unwrap_or(TOLERANCE);
+            let pursuit = PurePursuit::new(waypoints, REVERSE_LOOKAHEAD, constraints, tolerance);
+            let timeout = timeout.unwrap_or(follow_timeout(pursuit.length(), constraints));
+
+            follow(dt, Reversed(pursuit), timeout).await;
         }
-        // replace this with pure pursuit
-        Command::CubicBezier(_p0, _p1, _p2, _p3) => {
-            //let curve = CubicBezier::new(p0, p1, p2, p3);
-            //let trajectory = Trajectory::generate(curve, 0.1, CONSTRAINTS);
-            //
-            //ramsete.follow(dt, trajectory).await;
+        Command::CubicBezier(p0, p1, p2, p3, reverse) => {
+            let curve = CubicBezier::new(p0, p1, p2, p3);
+            let trajectory = Trajectory::generate(&curve, 0.5, constraints);
+            let tolerance = modifiers.tolerance.unwrap_or(TOLERANCE);
+            let ramsete = Ramsete::new(&trajectory, RAMSETE_GAINS, TRACK_WIDTH, tolerance);
+
+            // RAMSETE finishes on time or shortly after, the timeout is only there as a backstop
+            let timeout = timeout.unwrap_or(Duration::from_secs_f64(trajectory.duration() + 1.0));
+            if reverse {
+                follow(dt, Reversed(ramsete), timeout).await;
+            } else {
+                follow(dt, ramsete, timeout).await;
+            }
+        }
+        Command::SwingTo(angle, side) => {
+            // Turning around the held side's wheels
+            let center = match side {
+                Side::Left => TRACK_WIDTH / 2.0,
+                Side::Right => -TRACK_WIDTH / 2.0,
+            };
+            let target = (-angle).to_radians();
+            execute_arc(dt, drive_motors, target, center, limits, modifiers).await;
+        }
+        Command::ArcTo(angle, radius) => {
+            // Curve around a point on the side the robot is turning towards
+            let target = (-angle).to_radians();
+            let turn = wrap_angle(target - dt.tracking.heading());
+            let center = radius.copysign(turn);
+            execute_arc(dt, drive_motors, target, center, limits, modifiers).await;
+        }
+        Command::MoveToPose(target, angle, lead, reverse) => {
+            let gains = BoomerangGains {
+                lead: lead.unwrap_or(BOOMERANG_GAINS.lead),
+                ..BOOMERANG_GAINS
+            };
+            // Backing in, the robot travels the opposite way it ends up facing
+            let heading = if reverse { angle + 180.0 } else { angle };
+            let boomerang = Boomerang::new(
+                target,
+                (-heading).to_radians(),
+                gains,
+                constraints.max_velocity,
+                TRACK_WIDTH,
+                modifiers.tolerance.unwrap_or(TOLERANCE),
+            );
+
+            let timeout = timeout.unwrap_or(Duration::from_millis(3000));
+            if reverse {
+                follow(dt, Reversed(boomerang), timeout).await;
+            } else {
+                follow(dt, boomerang, timeout).await;
+            }
+        }
+        Command::DriveBy(distance, None) => {
+            let mut basic = limits.basic(modifiers);
+            basic.drive_distance(dt, distance).await;
+        }
+        Command::DriveBy(distance, Some(profile)) => {
+            let mut constraints = profile_constraints(DRIVE_PROFILE, profile);
+            constraints.max_velocity = limits.max_velocity(constraints.max_velocity);
+            let profile = MotionProfile::new(distance, constraints);
+            let start = dt.tracking.forward_travel();
+            let heading = dt.tracking.heading();
+
+            follow_profile(
+                dt,
+                &profile,
+                DRIVE_GAINS,
+                timeout,
+                |dt| dt.tracking.forward_travel() - start,
+                |dt, voltage| {
+                    // Hold the heading the drive started at
+                    let drift = wrap_angle(heading - dt.tracking.heading());
+                    let correction = HEADING_KP * drift;
+                    (voltage - correction, voltage + correction)
+                },
+            )
+            .await;
+        }
+        Command::TurnTo(angle, None) => {
+            let mut basic = limits.basic(modifiers);
+            basic.turn_to_heading(dt, (-angle).to_radians()).await;
+        }
+        Command::TurnTo(angle, Some(profile)) => {
+            let start = dt.tracking.heading();
+            let turn = wrap_angle((-angle).to_radians() - start);
+            let mut constraints = profile_constraints(TURN_PROFILE, profile);
+            constraints.max_velocity = limits.max_turn_velocity(constraints.max_velocity);
+            let profile = MotionProfile::new(turn, constraints);
+            let mut last_heading = start;
+            let mut turned = 0.0;
+
+            follow_profile(
+                dt,
+                &profile,
+                TURN_GAINS,
+                timeout,
+                |dt| {
+                    // Added up a tick at a time so a turn near 180 degrees keeps
+                    // counting the same way once it passes halfway around
+                    let heading = dt.tracking.heading();
+                    turned += wrap_angle(heading - last_heading);
+                    last_heading = heading;
+                    turned
+                },
+                |_, voltage| (-voltage, voltage),
+            )
+            .await;
+        }
+        // Speed limits last for the rest of the routine
+        Command::Speed(speed) => settings.speed = speed,
+        Command::TurnSpeed(speed) => settings.turn_speed = speed,
+        Command::Sleep(delay) => sleep(Duration::from_millis(delay)).await,
+        _ => (),
+    }
+}
+
+/// Drives through a `pursue` block's waypoints, starting from wherever the robot is now
+async fn execute_pursuit<M: MotorDevice>(
+    dt: &mut Drivetrain<M>,
+    drive_motors: &DriveMotors<M>,
+    lookahead: f64,
+    speed: f64,
+    path: &[Command],
+    report: &mut Report,
+) {
+    let mut waypoints = vec![dt.tracking.position()];
+    let mut reverse = false;
+    for &command in path {
+        match command {
+            Command::Coordinate(point, backwards) => {
+                waypoints.push(point);
+                reverse = backwards;
+            }
+            Command::CubicBezier(p0, p1, p2, p3, backwards) => {
+                reverse = backwards;
+                // Curves are followed through points about every 2 inches along them
+                let curve = CubicBezier::new(p0, p1, p2, p3);
+                let steps = ((curve.length() / 2.0).ceil() as usize).max(1);
+                waypoints.extend(curve.sample(steps));
+            }
+            _ => (),
         }
-        Command::DriveBy(distance) => {
-            _ = basic.drive_distance(dt, distance).await;
-            sleep(Duration::from_millis(250)).await;
+    }
+
+    let constraints = Constraints {
+        max_velocity: speed * DRIVE_SPEED,
+        ..CONSTRAINTS
+    };
+    let target = Target::Position(waypoints[waypoints.len() - 1]);
+    let pursuit = PurePursuit::new(waypoints, lookahead, constraints, TOLERANCE);
+    let timeout = follow_timeout(pursuit.length(), constraints);
+
+    let started = report.start.elapsed();
+    let start = Instant::now();
+    // Every waypoint in a block drives the same way
+    let still_for = if reverse {
+        watch_wheels(drive_motors, follow(dt, Reversed(pursuit), timeout)).await
+    } else {
+        watch_wheels(drive_motors, follow(dt, pursuit, timeout)).await
+    };
+    let duration = start.elapsed();
+
+    let (outcome, error) = judge(dt, drive_motors, target, TOLERANCE, still_for);
+    report.record(MotionResult {
+        command: Command::Pursuit(lookahead, speed, path.len()),
+        outcome,
+        error,
+        started,
+        duration,
+    });
+}
+
+/// How long a follower gets to drive a path before giving up: twice as long as
+/// it would take at top speed, plus a second to get going
+fn follow_timeout(length: f64, constraints: Constraints) -> Duration {
+    Duration::from_secs_f64(2.0 * length / constraints.max_velocity + 1.0)
+}
+
+/// Drives the robot with a follower until it reaches the end of its path, or
+/// until the timeout runs out
+async fn follow(
+    dt: &mut Drivetrain<impl MotorDevice>,
+    mut follower: impl Follower,
+    timeout: Duration,
+) {
+    let start = Instant::now();
+    let mut last_update = start;
+    while start.elapsed() < timeout {
+        sleep(Duration::from_millis(10)).await;
+        let elapsed = last_update.elapsed().as_secs_f64();
+        last_update = Instant::now();
+
+        let position = dt.tracking.position();
+        let heading = dt.tracking.heading();
+        let Some((left, right)) = follower.update(position, heading, elapsed) else {
+            break;
+        };
+
+        _ = dt.set_voltages((wheel_voltage(left), wheel_voltage(right)));
+    }
+
+    _ = dt.set_voltages((0.0, 0.0));
+}
+
+/// Voltage to drive the wheels at a speed (inches/second): how close it is to
+/// top speed, plus enough to get past friction so slow speeds still move
+fn wheel_voltage(speed: f64) -> f64 {
+    DRIVE_GAINS.kv * speed
+        + if speed == 0.0 {
+            0.0
+        } else {
+            DRIVE_GAINS.ks.copysign(speed)
         }
-        Command::TurnTo(angle) => {
-            _ = basic.turn_to_heading(dt, -angle.deg()).await;
-            sleep(Duration::from_millis(250)).await;
+}
+
+/// Turns to a heading with the angular controller while driving around a point
+/// `center` inches to the robot's left (or right if negative). A center of
+/// zero turns in place, and half the track width keeps one side still.
+async fn execute_arc<M: MotorDevice>(
+    dt: &mut Drivetrain<M>,
+    drive_motors: &DriveMotors<M>,
+    heading: f64,
+    center: f64,
+    limits: MotionSettings,
+    modifiers: Modifiers,
+) {
+    let mut controller = ANGULAR_CONTROLLER;
+    let half_track = TRACK_WIDTH / 2.0;
+    let timeout = Duration::from_millis(modifiers.timeout.unwrap_or(2000));
+    let tolerance = modifiers.tolerance.unwrap_or(TOLERANCE).to_radians();
+    let settle_time = Duration::from_millis(100);
+
+    // How far the left and right wheels are from the center, which is how far
+    // each side rolls for every radian the robot turns (inches)
+    let radii = [center - half_track, center + half_track];
+    // Fastest turn that keeps the outer side under the speed limit, leaving it
+    // room to catch up (radians/second)
+    let max_speed = limits.turn_speed * CONSTRAINTS.max_velocity;
+    let max_rate = max_speed / radii[0].abs().max(radii[1].abs());
+
+    let start_travel = side_travel(drive_motors);
+    let mut last_heading = dt.tracking.heading();
+    let mut turned = 0.0;
+
+    let start = Instant::now();
+    let mut last_update = start;
+    let mut settled_since = None;
+    while start.elapsed() < timeout {
+        sleep(Duration::from_millis(10)).await;
+        let elapsed = last_update.elapsed();
+        last_update = Instant::now();
+
+        let current = dt.tracking.heading();
+        let error = wrap_angle(heading - current);
+        if error.abs() < tolerance {
+            let since = *settled_since.get_or_insert(last_update);
+            if since.elapsed() > settle_time {
+                break;
+            }
+        } else {
+            settled_since = None;
         }
-        // speed can be controlled using the `velocity_tolerance` constraint in the settler
-        Command::Speed(max_speed) => {
-            let output_limit = Some(Motor::V5_MAX_VOLTAGE * max_speed);
 
-            basic.linear_controller.set_output_limit(output_limit);
+        // Full output from the heading controller turns as fast as allowed
+        let output = controller.update(error, elapsed);
+        let rate = (output / MAX_VOLTAGE * max_rate).clamp(-max_rate, max_rate);
+
+        turned += wrap_angle(current - last_heading);
+        last_heading = current;
+
+        // Each side drives at its speed around the center, and catches up to
+        // where it should be for how far the robot has turned so it stays on
+        // the arc. A side sitting on the center holds still for the other to
+        // swing around.
+        let travel = side_travel(drive_motors);
+        for (i, side) in drive_motors.iter().enumerate() {
+            let behind = start_travel[i] + turned * radii[i] - travel[i];
+            let speed = rate * radii[i] + ARC_KP * behind;
+            for motor in side.borrow_mut().iter_mut() {
+                _ = if radii[i] == 0.0 {
+                    motor.brake(BrakeMode::Hold)
+                } else {
+                    motor.set_voltage(wheel_voltage(speed))
+                };
+            }
         }
+    }
+
+    _ = dt.set_voltages((0.0, 0.0));
+}
+
+/// Picks the jerk limit for the requested profile shape
+fn profile_constraints(constraints: ProfileConstraints, profile: Profile) -> ProfileConstraints {
+    match profile {
+        Profile::Trapezoidal => ProfileConstraints {
+            max_jerk: None,
+            ..constraints
+        },
+        Profile::SCurve => constraints,
+    }
+}
+
+/// Follows a motion profile on the clock, then holds the end point briefly to
+/// settle, unless the timeout runs out first. `measure` reads how far along
+/// the robot is, and `output` splits the voltage to follow the profile between
+/// the left and right sides.
+async fn follow_profile<M: MotorDevice>(
+    dt: &mut Drivetrain<M>,
+    profile: &MotionProfile,
+    gains: ProfileGains,
+    timeout: Option<Duration>,
+    mut measure: impl FnMut(&Drivetrain<M>) -> f64,
+    output: impl Fn(&Drivetrain<M>, f64) -> (f64, f64),
+) {
+    let full = Duration::from_secs_f64(profile.duration()) + Duration::from_millis(250);
+    let duration = timeout.map_or(full, |timeout| full.min(timeout));
+
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let setpoint = profile.sample(start.elapsed().as_secs_f64());
+        let voltage = gains.voltage(setpoint, measure(dt));
+        _ = dt.set_voltages(output(dt, voltage));
+
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    _ = dt.set_voltages((0.0, 0.0));
+}
+
+/// Runs a command that controls one of the robot's mechanisms
+async fn execute_action(
+    intake: &mut Intake,
+    lady_brown: &mut LadyBrown,
+    clamp: &mut (impl PneumaticDevice, impl PneumaticDevice),
+    command: Command,
+) {
+    match command {
         Command::IntakeCommand(cmd) => {
-            robot.intake.set_command(cmd);
+            intake.set_command(cmd);
         }
         Command::LadyBrownCommand(cmd) => {
-            robot.lady_brown.set_command(cmd);
+            lady_brown.set_command(cmd);
         }
         Command::ToggleClamp => {
-            _ = robot.clamp.0.toggle();
-            _ = robot.clamp.1.toggle();
+            _ = clamp.0.toggle();
+            _ = clamp.1.toggle();
         }
         Command::NextLBStage => {
-            robot.lady_brown.next();
+            lady_brown.next();
         }
         Command::Sleep(delay) => {
             sleep(Duration::from_millis(delay)).await;
@@ -74,3 +1100,98 @@ pub async fn execute_command(robot: &mut Robot, command: Command, basic: &mut Ba
         _ => (),
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::{
+        config::{Color, GEARING, RobotSettings},
+        hardware::mock::{Mock, MockImu, MockMotor, Mocks},
+        platform::run,
+        robot::Devices,
+        subsystems::command::IntakeCommand,
+    };
+
+    /// How fast the mock drive motors spin at full voltage (rpm)
+    const FREE_SPEED: f64 = 600.0;
+
+    /// Moves the drive motors at a speed proportional to their voltage, and
+    /// turns the inertial sensor by how far apart the sides have rolled
+    fn drive(sides: &[[MockMotor; 3]; 2], imu: &MockImu, dt: Duration) {
+        let mut travel = [0.0; 2];
+        for (side, motors) in sides.iter().enumerate() {
+            for motor in motors {
+                let mut motor = motor.state();
+                motor.velocity = if motor.brake.is_none() {
+                    FREE_SPEED * motor.voltage / motor.max_voltage
+                } else {
+                    0.0
+                };
+                motor.position += motor.velocity / 60.0 * dt.as_secs_f64();
+                travel[side] += motor.position * GEARING * WHEEL_DIAMETER * PI / 3.0;
+            }
+        }
+
+        // The sensor reads clockwise degrees
+        imu.state().heading = -((travel[1] - travel[0]) / TRACK_WIDTH).to_degrees();
+    }
+
+    #[test]
+    fn runs_actions_then_drives_to_the_target() {
+        let left = <[MockMotor; 3]>::default();
+        let right = <[MockMotor; 3]>::default();
+        let imu = MockImu::default();
+        let intake_motor = MockMotor::default();
+        let clamp = (Mock::default(), Mock::default());
+        let devices = Devices::<Mocks> {
+            imu: imu.clone(),
+            left_motors: left.clone(),
+            right_motors: right.clone(),
+            intake_motor: intake_motor.clone(),
+            optical_sensor: Mock::default(),
+            doinker: Mock::default(),
+            lady_brown_motor: Mock::default(),
+            lady_brown_rotation: Mock::default(),
+            clamp: (clamp.0.clone(), clamp.1.clone()),
+        };
+        let settings = Rc::new(RefCell::new(RobotSettings {
+            auton_path: vec![
+                Command::Pose(Vec2::new(0.0, 0.0), 0.0),
+                Command::ToggleClamp,
+                Command::IntakeCommand(IntakeCommand::On),
+                Command::DriveBy(12.0, None),
+            ],
+            test_auton: false,
+            curr_color: Color::Red,
+        }));
+
+        let sides = [left, right];
+        let mut report = Report::default();
+        run(
+            async {
+                let mut robot = Robot::new(devices, settings).await;
+                robot.run_auton(&mut report).await;
+                robot
+            },
+            Duration::from_secs(5),
+            |dt| drive(&sides, &imu, dt),
+        )
+        .expect("the path should finish");
+
+        assert!(clamp.0.state().extended && clamp.1.state().extended);
+        assert_eq!(intake_motor.state().voltage, MAX_VOLTAGE);
+
+        let [result] = report.results[..] else {
+            panic!("expected one motion, got {:?}", report.results);
+        };
+        assert_eq!(result.outcome, Outcome::Settled);
+        assert!(
+            result.error < TOLERANCE,
+            "ended {} inches off",
+            result.error
+        );
+    }
+}
@@ -0,0 +1,11 @@
+/// Shared pieces of routines that a path can pull in with `include "name"`
+pub static FRAGMENTS: [(&str, &str); 1] =
+    [("Common", include_str!("paths/fragments/Common.botpath"))];
+
+/// Looks up a fragment by name, returning its name and contents
+pub fn find(name: &str) -> Option<(&'static str, &'static str)> {
+    FRAGMENTS
+        .iter()
+        .find(|(fragment, _)| *fragment == name)
+        .copied()
+}
@@ -1,6 +1,10 @@
+mod basic;
 pub mod command;
+pub mod error;
 pub mod execute;
+pub mod fragments;
 mod parse;
+pub mod report;
 
 /// A list of commands for the robot to execute
 pub static PATHS: [&str; 6] = [
@@ -11,4 +15,3 @@ pub static PATHS: [&str; 6] = [
     include_str!("paths/Right.botpath"),
     include_str!("paths/Skills.botpath"),
 ];
-
@@ -1,78 +1,568 @@
-use alloc::vec::Vec;
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
-use evian::math::Vec2;
+use super::{
+    command::{Command, Condition, Failure, Modifiers, Profile, Progress, Side},
+    error::{ArgError, ParseError},
+};
+use crate::{
+    pose::Vec2,
+    subsystems::command::{IntakeCommand, LadyBrownCommand},
+};
 
-use crate::subsystems::{intake::IntakeCommand, lady_brown::LadyBrownCommand};
+/// A single line of a path file that holds a command, split into tokens
+#[derive(Clone)]
+pub struct Line<'a> {
+    number: usize,
+    tokens: Vec<&'a str>,
+    columns: Vec<usize>,
+}
+
+impl<'a> Line<'a> {
+    /// Splits a line into tokens, returning `None` for empty lines and comments
+    pub fn new(number: usize, text: &'a str) -> Option<Self> {
+        let trimmed = text.trim();
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            return None;
+        }
+
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        // Keep the column each token starts at so errors can point at it
+        let columns = tokens
+            .iter()
+            .map(|token| token.as_ptr() as usize - text.as_ptr() as usize + 1)
+            .collect();
+
+        Some(Self {
+            number,
+            tokens,
+            columns,
+        })
+    }
+
+    /// The first token of the line, naming the command
+    pub fn action(&self) -> &'a str {
+        self.tokens[0]
+    }
 
-use super::command::Command;
+    /// Every token after the command name
+    pub fn args(&self) -> &[&'a str] {
+        &self.tokens[1..]
+    }
+
+    /// Checks if the line starts a `{ ... }` block
+    pub fn opens_block(&self) -> bool {
+        self.tokens.last() == Some(&"{")
+    }
+
+    /// Places an argument error at its position in the file
+    pub fn error(&self, e: ArgError) -> ParseError {
+        // Point at the offending argument, or at the command itself
+        let token = e.arg.map_or(0, |arg| arg + 1);
+        ParseError {
+            fragment: None,
+            line: self.number,
+            column: self.columns[token],
+            token: self.tokens[token].to_string(),
+            command: self.action().to_string(),
+            expected: e.expected,
+            found: e.found,
+        }
+    }
+}
+
+/// Splits a path into the lines that hold commands, numbering them as they
+/// appear in the file
+pub fn lines(path: &str) -> Vec<Line<'_>> {
+    path.lines()
+        .enumerate()
+        .filter_map(|(index, text)| Line::new(index + 1, text))
+        .collect()
+}
+
+/// Named values declared with `let NAME = value`, which can be used in place
+/// of any float, integer or coordinate argument
+#[derive(Clone, Default)]
+pub struct Variables(BTreeMap<String, String>);
+
+impl Variables {
+    /// Declares a variable from the arguments of a `let` line
+    pub fn declare(&mut self, args: &[&str]) -> Result<(), ArgError> {
+        if args.len() != 3 {
+            return Err(ArgError::count("`NAME = value`", args.len()));
+        }
+
+        let (name, value) = (args[0], args[2]);
+        if !is_name(name) {
+            return Err(ArgError::invalid("a variable name", name).at(0));
+        }
+        if args[1] != "=" {
+            return Err(ArgError::invalid("`=`", args[1]).at(1));
+        }
+
+        self.set(name, value).map_err(|e| e.at(2))
+    }
+
+    /// Sets a variable, making sure its value is a float or coordinate
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), ArgError> {
+        // Variables may be built from earlier ones, e.g. `let GOAL = (X,96)`
+        let value = self.substitute(value);
+        if float(&value).is_err() && vec2(&value).is_err() {
+            return Err(ArgError::invalid("a float or coordinate", &value));
+        }
+
+        self.0.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// Replaces an argument, or the values inside a coordinate, with the
+    /// variables they name
+    pub fn substitute(&self, arg: &str) -> String {
+        if let Some(value) = self.0.get(arg) {
+            return value.clone();
+        }
+
+        if let Some(inner) = arg.strip_prefix('(').and_then(|a| a.strip_suffix(')')) {
+            let values: Vec<String> = inner
+                .split(',')
+                .map(|value| self.substitute(value.trim()))
+                .collect();
+            return format!("({})", values.join(","));
+        }
+
+        arg.to_string()
+    }
+}
+
+/// Parses the `name(param, ...)` part of a macro definition into the macro's
+/// name and parameter names
+pub fn macro_signature(args: &[&str]) -> Result<(String, Vec<String>), ArgError> {
+    if args.is_empty() {
+        return Err(ArgError::count("a macro name and parameters", 0));
+    }
+
+    // Parameters may be spread over several tokens, e.g. `grab_goal(distance, delay)`
+    let signature = args.concat();
+    let (name, params) = match signature.split_once('(') {
+        Some((name, params)) => {
+            let params = params
+                .strip_suffix(')')
+                .ok_or_else(|| ArgError::invalid("a closing `)`", &signature))?;
+            (name, params.split(',').map(str::trim).collect())
+        }
+        None => (signature.as_str(), Vec::new()),
+    };
+
+    if !is_name(name) {
+        return Err(ArgError::invalid("a macro name", name).at(0));
+    }
+
+    // Allow `name()` for macros without any parameters
+    let params: Vec<&str> = params.into_iter().filter(|p| !p.is_empty()).collect();
+    if let Some(param) = params.iter().find(|param| !is_name(param)) {
+        return Err(ArgError::invalid("a parameter name", param));
+    }
+
+    Ok((
+        name.to_string(),
+        params.into_iter().map(ToString::to_string).collect(),
+    ))
+}
+
+/// Parses the quoted name given to `include`, e.g. `include "Left opening"`
+pub fn fragment_name(args: &[&str]) -> Result<String, ArgError> {
+    if args.is_empty() {
+        return Err(ArgError::count("a quoted fragment name", 0));
+    }
+
+    // Names may contain spaces, so they span every argument
+    let name = args.join(" ");
+    name.strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+        .ok_or_else(|| ArgError::invalid("a quoted fragment name", &name).at(0))
+}
+
+/// Most passes a `repeat` block can make. Every pass is unrolled into the path,
+/// so a huge count would run the Brain out of memory.
+const MAX_REPEAT: u64 = 100;
+
+/// Parses the arguments of `repeat N {` or `repeat N as counter {` into the
+/// number of passes and the name of the loop counter, if there is one. `count`
+/// is the first argument with any variable already substituted.
+pub fn repeat(count: &str, args: &[&str]) -> Result<(u64, Option<String>), ArgError> {
+    let (counter, brace) = match *args {
+        [_, brace] => (None, brace),
+        [_, "as", counter, brace] if is_name(counter) => (Some(counter.to_string()), brace),
+        [_, "as", counter, _] => return Err(ArgError::invalid("a counter name", counter).at(2)),
+        [_, word, _, _] => return Err(ArgError::invalid("`as`", word).at(1)),
+        _ => return Err(ArgError::count("`N {` or `N as counter {`", args.len())),
+    };
+    if brace != "{" {
+        return Err(ArgError::invalid("`{` to open the block", brace).at(args.len() - 1));
+    }
+
+    let count = count
+        .parse::<u64>()
+        .map_err(|_| ArgError::invalid("a number of passes", count).at(0))?;
+    if count > MAX_REPEAT {
+        return Err(ArgError::invalid("at most 100 passes", &count.to_string()).at(0));
+    }
+
+    Ok((count, counter))
+}
+
+/// Parses the settings of a `pursue` block: how far ahead to aim (inches) and
+/// the fraction of top speed to drive at, like `pursue 12 0.6 {`
+pub fn pursue(args: &[&str]) -> Result<(f64, f64), ArgError> {
+    let [lookahead, speed, brace] = *args else {
+        return Err(ArgError::count(
+            "a lookahead distance, a speed and `{`",
+            args.len(),
+        ));
+    };
+    if brace != "{" {
+        return Err(ArgError::invalid("`{` to open the block", brace).at(2));
+    }
+
+    let lookahead = float(lookahead)
+        .ok()
+        .filter(|lookahead| *lookahead > 0.0)
+        .ok_or_else(|| ArgError::invalid("a positive lookahead distance", lookahead).at(0))?;
+    let speed = speed_limit(speed).map_err(|e| e.at(1))?;
+
+    Ok((lookahead, speed))
+}
+
+/// Parses the condition of an `if` line, like `if ring == opponent {`
+pub fn condition(args: &[&str]) -> Result<Condition, ArgError> {
+    let Some(condition) = args.strip_suffix(&["{"]) else {
+        return Err(match args.last() {
+            Some(last) => ArgError::invalid("`{` to open the block", last).at(args.len() - 1),
+            None => ArgError::count("a condition", 0),
+        });
+    };
+
+    state(condition)
+}
+
+/// Parses a `WaitUntil` command: the condition to wait for, then how long to
+/// wait for it before moving on (milliseconds), like `WaitUntil settled 1000`
+pub fn wait_until(args: &[&str]) -> Result<(Condition, u64), ArgError> {
+    let Some((timeout, condition)) = args.split_last() else {
+        return Err(ArgError::count("a condition and a timeout", 0));
+    };
+
+    let timeout = timeout
+        .parse::<u64>()
+        .map_err(|_| ArgError::invalid("a timeout in milliseconds", timeout).at(args.len() - 1))?;
+
+    Ok((state(condition)?, timeout))
+}
+
+/// Parses a piece of robot state to check, like `ring == opponent`
+fn state(condition: &[&str]) -> Result<Condition, ArgError> {
+    match *condition {
+        ["clamped"] => Ok(Condition::Clamped),
+        ["settled"] => Ok(Condition::Settled),
+        ["failed"] => Ok(Condition::Failed),
+        ["ring", "==", "alliance"] => Ok(Condition::AllianceRing),
+        ["ring", "==", "opponent"] => Ok(Condition::OpponentRing),
+        ["ring", "==", "any"] => Ok(Condition::AnyRing),
+        ["ring", "==", "none"] => Ok(Condition::NoRing),
+        ["ring", "==", ring] => {
+            Err(ArgError::invalid("`alliance`, `opponent`, `any` or `none`", ring).at(2))
+        }
+        ["lady_brown", comparison @ (">" | "<"), angle] => {
+            let angle =
+                float(angle).map_err(|_| ArgError::invalid("an angle in degrees", angle).at(2))?;
+            Ok(match comparison {
+                ">" => Condition::LadyBrownAbove(angle),
+                _ => Condition::LadyBrownBelow(angle),
+            })
+        }
+        ["elapsed", comparison @ (">" | "<"), time] => {
+            let time = time
+                .parse::<u64>()
+                .map_err(|_| ArgError::invalid("a time in milliseconds", time).at(2))?;
+            Ok(match comparison {
+                ">" => Condition::ElapsedOver(time),
+                _ => Condition::ElapsedUnder(time),
+            })
+        }
+        [first, ..] => Err(ArgError::invalid(
+            "`clamped`, `settled`, `failed`, `ring == ...`, `lady_brown > ...` or `elapsed > ...`",
+            first,
+        )
+        .at(0)),
+        [] => Err(ArgError::count("a condition", 0)),
+    }
+}
+
+/// Parses what an `onfail` line does when a motion fails
+pub fn on_fail(args: &[&str]) -> Result<Failure, ArgError> {
+    match *args {
+        ["continue"] => Ok(Failure::Continue),
+        ["abort"] => Ok(Failure::Abort),
+        ["fallback"] => Ok(Failure::Fallback),
+        [arg] => Err(ArgError::invalid("`continue`, `abort` or `fallback`", arg).at(0)),
+        _ => Err(ArgError::count(
+            "`continue`, `abort` or `fallback`",
+            args.len(),
+        )),
+    }
+}
+
+/// Parses where along a motion a marker fires: `40%` of the way, `12` inches
+/// from the start, or `-2` inches before the end
+pub fn progress(arg: &str) -> Result<Progress, ArgError> {
+    if let Some(percent) = arg.strip_suffix('%') {
+        let percent = float(percent)
+            .ok()
+            .filter(|percent| (0.0..=100.0).contains(percent))
+            .ok_or_else(|| ArgError::invalid("a percentage from 0% to 100%", arg))?;
+        return Ok(Progress::Fraction(percent / 100.0));
+    }
+
+    let distance = float(arg).map_err(|_| ArgError::invalid("a distance or percentage", arg))?;
+    if distance.is_sign_negative() {
+        Ok(Progress::FromEnd(-distance))
+    } else {
+        Ok(Progress::FromStart(distance))
+    }
+}
+
+/// Checks if a token can be used as a variable name
+fn is_name(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
 
 /// Parses a `Vec2<f64>` from a string formatted as "(x,y)"
-fn vec2(arg: &str) -> Result<Vec2<f64>, &'static str> {
+fn vec2(arg: &str) -> Result<Vec2<f64>, ArgError> {
     let arg = arg.trim();
     if !arg.starts_with('(') || !arg.ends_with(')') {
-        return Err("Invalid Vec2 format");
+        return Err(ArgError::invalid("a coordinate like (x,y)", arg));
     }
 
     let coords: Vec<&str> = arg[1..arg.len() - 1].split(',').map(|s| s.trim()).collect();
     if coords.len() != 2 {
-        return Err("Vec2 must contain two values");
+        return Err(ArgError::invalid("a coordinate with two values", arg));
     }
 
-    let x = coords[0]
-        .parse::<f64>()
-        .map_err(|_| "Invalid float for Vec2")?;
-    let y = coords[1]
-        .parse::<f64>()
-        .map_err(|_| "Invalid float for Vec2")?;
+    let x = float(coords[0])?;
+    let y = float(coords[1])?;
 
     Ok(Vec2::new(x, y))
 }
 
-pub fn single_vec2(args: &[&str]) -> Result<Vec2<f64>, &'static str> {
+/// Parses a single float, keeping the token around for error messages
+fn float(arg: &str) -> Result<f64, ArgError> {
+    arg.parse::<f64>()
+        .map_err(|_| ArgError::invalid("a float", arg))
+}
+
+/// Parses a fraction of the robot's top speed to limit it to
+fn speed_limit(arg: &str) -> Result<f64, ArgError> {
+    float(arg)
+        .ok()
+        .filter(|speed| *speed > 0.0 && *speed <= 1.0)
+        .ok_or_else(|| ArgError::invalid("a speed above 0.0 and up to 1.0", arg))
+}
+
+pub fn single_vec2(args: &[&str]) -> Result<Vec2<f64>, ArgError> {
     if args.len() != 1 {
-        return Err("Expected one argument for Vec2");
+        return Err(ArgError::count("one coordinate argument", args.len()));
     }
-    vec2(args[0])
+    vec2(args[0]).map_err(|e| e.at(0))
 }
 
-pub fn multiple_vec2(args: &[&str], expected: usize) -> Result<Vec<Vec2<f64>>, &'static str> {
+pub fn multiple_vec2(args: &[&str], expected: usize) -> Result<Vec<Vec2<f64>>, ArgError> {
     if args.len() != expected {
-        return Err("Incorrect number of Vec2 arguments");
+        return Err(ArgError::count(
+            "one coordinate per control point",
+            args.len(),
+        ));
     }
 
-    args.iter().map(|&arg| vec2(arg)).collect()
+    args.iter()
+        .enumerate()
+        .map(|(i, &arg)| vec2(arg).map_err(|e| e.at(i)))
+        .collect()
 }
 
-pub fn single_f64(args: &[&str]) -> Result<f64, &'static str> {
+/// Parses the limit set by `Speed` or `TurnSpeed`
+pub fn single_speed(args: &[&str]) -> Result<f64, ArgError> {
     if args.len() != 1 {
-        return Err("Expected one float argument");
+        return Err(ArgError::count("one speed argument", args.len()));
     }
-    args[0].parse::<f64>().map_err(|_| "Invalid float argument")
+    speed_limit(args[0]).map_err(|e| e.at(0))
 }
 
-pub fn single_u64(args: &[&str]) -> Result<u64, &'static str> {
+/// Parses a distance or angle, optionally followed by the motion profile to
+/// drive it with, like `Drive 24 scurve`
+pub fn profiled_f64(args: &[&str]) -> Result<(f64, Option<Profile>), ArgError> {
+    let profile = match args {
+        [_] => None,
+        [_, "trapezoid"] => Some(Profile::Trapezoidal),
+        [_, "scurve"] => Some(Profile::SCurve),
+        [_, profile] => {
+            return Err(ArgError::invalid("`trapezoid` or `scurve`", profile).at(1));
+        }
+        _ => {
+            return Err(ArgError::count(
+                "one float argument and an optional profile",
+                args.len(),
+            ));
+        }
+    };
+
+    Ok((float(args[0]).map_err(|e| e.at(0))?, profile))
+}
+
+pub fn single_u64(args: &[&str]) -> Result<u64, ArgError> {
     if args.len() != 1 {
-        return Err("Expected one integer argument");
+        return Err(ArgError::count("one integer argument", args.len()));
     }
     args[0]
         .parse::<u64>()
-        .map_err(|_| "Invalid integer argument")
+        .map_err(|_| ArgError::invalid("an integer", args[0]).at(0))
 }
 
-pub fn pose(args: &[&str]) -> Result<Command, &'static str> {
+pub fn pose(args: &[&str]) -> Result<Command, ArgError> {
     if args.len() != 2 {
-        return Err("Pose requires two arguments: (x,y) and angle");
+        return Err(ArgError::count(
+            "two arguments: (x,y) and angle",
+            args.len(),
+        ));
     }
 
-    let position = vec2(args[0])?;
-    let angle = single_f64(&[args[1]])?;
+    let position = vec2(args[0]).map_err(|e| e.at(0))?;
+    let angle = float(args[1]).map_err(|e| e.at(1))?;
 
     Ok(Command::Pose(position, angle))
 }
 
-pub fn intake_command(args: &[&str]) -> Result<IntakeCommand, &'static str> {
+/// Parses a `MoveToPose` target: `(x,y) angle`, optionally followed by how far
+/// to lead the robot out along the final heading
+pub fn move_to_pose(args: &[&str]) -> Result<(Vec2<f64>, f64, Option<f64>), ArgError> {
+    if !(2..=3).contains(&args.len()) {
+        return Err(ArgError::count(
+            "(x,y), angle and an optional lead",
+            args.len(),
+        ));
+    }
+
+    let position = vec2(args[0]).map_err(|e| e.at(0))?;
+    let angle = float(args[1]).map_err(|e| e.at(1))?;
+    let lead = match args.get(2) {
+        Some(lead) => Some(
+            float(lead)
+                .ok()
+                .filter(|lead| (0.0..=1.0).contains(lead))
+                .ok_or_else(|| ArgError::invalid("a lead from 0.0 to 1.0", lead).at(2))?,
+        ),
+        None => None,
+    };
+
+    Ok((position, angle, lead))
+}
+
+/// Splits a trailing `reverse` off of a move's arguments, which makes the robot
+/// drive it backwards
+pub fn reverse_flag<'a, 'b>(args: &'a [&'b str]) -> (&'a [&'b str], bool) {
+    match args {
+        [rest @ .., "reverse"] => (rest, true),
+        _ => (args, false),
+    }
+}
+
+/// Splits trailing modifiers like `timeout=800 speed=0.6 tol=1.0` off of a
+/// motion's arguments. Returns `None` if there weren't any.
+pub fn modifiers<'a, 'b>(
+    args: &'a [&'b str],
+) -> Result<(&'a [&'b str], Option<Modifiers>), ArgError> {
+    let count = args
+        .iter()
+        .rev()
+        .take_while(|arg| arg.contains('='))
+        .count();
+    let (args, tokens) = args.split_at(args.len() - count);
+    if tokens.is_empty() {
+        return Ok((args, None));
+    }
+
+    let mut modifiers = Modifiers::default();
+    for (i, token) in tokens.iter().enumerate() {
+        let at = args.len() + i;
+        let (key, value) = token.split_once('=').unwrap_or_default();
+        match key {
+            "timeout" => {
+                let timeout = value
+                    .parse::<u64>()
+                    .map_err(|_| ArgError::invalid("a timeout in milliseconds", value).at(at))?;
+                modifiers.timeout = Some(timeout);
+            }
+            "speed" => modifiers.speed = Some(speed_limit(value).map_err(|e| e.at(at))?),
+            "tol" => {
+                let tolerance = float(value)
+                    .ok()
+                    .filter(|tolerance| *tolerance > 0.0)
+                    .ok_or_else(|| ArgError::invalid("a positive tolerance", value).at(at))?;
+                modifiers.tolerance = Some(tolerance);
+            }
+            _ => return Err(ArgError::invalid("`timeout=`, `speed=` or `tol=`", token).at(at)),
+        }
+    }
+
+    Ok((args, Some(modifiers)))
+}
+
+/// Parses a `SwingTo` command: the angle to turn to and the side to hold still
+pub fn swing(args: &[&str]) -> Result<Command, ArgError> {
+    let [angle, side] = *args else {
+        return Err(ArgError::count(
+            "an angle and `left` or `right`",
+            args.len(),
+        ));
+    };
+
+    let angle = float(angle).map_err(|e| e.at(0))?;
+    let side = match side {
+        "left" => Side::Left,
+        "right" => Side::Right,
+        _ => return Err(ArgError::invalid("`left` or `right`", side).at(1)),
+    };
+
+    Ok(Command::SwingTo(angle, side))
+}
+
+/// Parses an `ArcTo` command: the angle to turn to and the radius to turn around
+pub fn arc(args: &[&str]) -> Result<Command, ArgError> {
+    let [angle, radius] = *args else {
+        return Err(ArgError::count("an angle and a radius", args.len()));
+    };
+
+    let angle = float(angle).map_err(|e| e.at(0))?;
+    let radius = float(radius)
+        .ok()
+        .filter(|radius| *radius > 0.0)
+        .ok_or_else(|| ArgError::invalid("a positive radius", radius).at(1))?;
+
+    Ok(Command::ArcTo(angle, radius))
+}
+
+pub fn intake_command(args: &[&str]) -> Result<IntakeCommand, ArgError> {
     if args.len() != 1 {
-        return Err("Expected one float/toggle argument");
+        return Err(ArgError::count("one float/toggle argument", args.len()));
     }
 
     let arg = args[0];
@@ -81,21 +571,23 @@ pub fn intake_command(args: &[&str]) -> Result<IntakeCommand, &'static str> {
     } else if arg == "on" {
         return Ok(IntakeCommand::On);
     }
-    
-    single_f64(&[arg]).map(IntakeCommand::Voltage)
+
+    float(arg)
+        .map(IntakeCommand::Voltage)
+        .map_err(|_| ArgError::invalid("a voltage, `on` or `off`", arg).at(0))
 }
 
-pub fn lady_brown_command(args: &[&str]) -> Result<LadyBrownCommand, &'static str> {
+pub fn lady_brown_command(args: &[&str]) -> Result<LadyBrownCommand, ArgError> {
     if args.len() != 1 {
-        return Err("Expected one voltage/next argument");
+        return Err(ArgError::count("one voltage/next argument", args.len()));
     }
 
     let arg = args[0];
     if arg == "next" {
         return Ok(LadyBrownCommand::Next);
-    } else if arg == "back" {
-        todo!();
     }
 
-    single_f64(&[arg]).map(LadyBrownCommand::Angle)
+    float(arg)
+        .map(LadyBrownCommand::Angle)
+        .map_err(|_| ArgError::invalid("an angle or `next`", arg).at(0))
 }
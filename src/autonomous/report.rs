@@ -0,0 +1,117 @@
+use alloc::vec::Vec;
+use core::{fmt, time::Duration};
+
+use super::command::Command;
+use crate::platform::{Instant, println};
+
+/// How a motion finished
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// The robot ended up on its target
+    Settled,
+
+    /// The motion gave up before the robot reached its target
+    TimedOut,
+
+    /// The wheels stopped turning short of the target, like when the robot is
+    /// pushing against a wall or another robot
+    Stalled,
+
+    /// A drive motor couldn't be reached, so the motion couldn't be trusted
+    Error,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Settled => "settled",
+            Outcome::TimedOut => "timed out",
+            Outcome::Stalled => "stalled",
+            Outcome::Error => "error",
+        })
+    }
+}
+
+/// How a single motion in a run went
+#[derive(Clone, Copy, Debug)]
+pub struct MotionResult {
+    pub command: Command,
+    pub outcome: Outcome,
+    /// How far from its target the robot ended up (inches, or degrees for turns)
+    pub error: f64,
+    /// When the motion started, since the start of the run
+    pub started: Duration,
+    /// How long the motion took
+    pub duration: Duration,
+}
+
+impl MotionResult {
+    /// Checks if the robot didn't make it to the motion's target
+    pub fn failed(&self) -> bool {
+        self.outcome != Outcome::Settled
+    }
+}
+
+impl fmt::Display for MotionResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>6.2}s {:?}: {} after {:.2}s, {:.2} off",
+            self.started.as_secs_f64(),
+            self.command,
+            self.outcome,
+            self.duration.as_secs_f64(),
+            self.error,
+        )
+    }
+}
+
+/// Every motion from one run of a path, in the order they finished
+pub struct Report {
+    pub start: Instant,
+    pub results: Vec<MotionResult>,
+    /// Set if a failure stopped the path early
+    pub aborted: bool,
+}
+
+impl Default for Report {
+    /// Starts an empty report for a run beginning now
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            results: Vec::new(),
+            aborted: false,
+        }
+    }
+}
+
+impl Report {
+    /// Adds a motion's result to the report, logging it as it comes in
+    pub fn record(&mut self, result: MotionResult) {
+        println!("{result}");
+        self.results.push(result);
+    }
+
+    /// Checks if the most recent motion didn't reach its target
+    pub fn last_failed(&self) -> bool {
+        self.results.last().is_some_and(MotionResult::failed)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failures = self.results.iter().filter(|result| result.failed()).count();
+        write!(
+            f,
+            "Path {} after {:.2}s, {} of {} motions failed",
+            if self.aborted { "aborted" } else { "finished" },
+            self.start.elapsed().as_secs_f64(),
+            failures,
+            self.results.len(),
+        )?;
+        for result in &self.results {
+            write!(f, "\n{result}")?;
+        }
+        Ok(())
+    }
+}
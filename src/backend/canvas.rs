@@ -1,17 +1,15 @@
 use alloc::{vec, vec::Vec};
 
-use evian::math::Vec2;
-use slint::{Image, SharedPixelBuffer};
-use vexide::prelude::Float;
+use crate::{autonomous::command::Command, config::Color, platform::Float, pose::Vec2};
 
-use crate::{autonomous::command::Command, Color};
-
-// Drawing platform for different lines and curves for the autonomous selector
+// Drawing platform for different lines and curves for the autonomous selector.
+// Kept free of Slint so routine previews can also be drawn off the Brain.
 pub struct Canvas {
     width: u32,
     height: u32,
-    buffer: Vec<u8>, // Stores pixel data in RGBA format
-    color: [u8; 4],  // Current drawing color
+    buffer: Vec<u8>,  // Stores pixel data in RGBA format
+    color: [u8; 4],   // Current drawing color
+    dash_step: usize, // Pixels drawn so far along a dashed line
 }
 
 impl Canvas {
@@ -27,10 +25,10 @@ impl Canvas {
                 Color::Red => [255u8, 0u8, 0u8, 255u8],
                 Color::Blue => [0u8, 0u8, 255u8, 255u8],
             },
+            dash_step: 0,
         }
     }
 
-
     /// Set a pixel at (x, y) to the current color
     fn set_pixel(&mut self, x: u32, y: u32) {
         if x >= self.width || y >= self.height {
@@ -40,8 +38,8 @@ impl Canvas {
         self.buffer[index..index + 4].copy_from_slice(&self.color);
     }
 
-    /// Draw a line using Bresenham's algorithm
-    fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+    /// Draw a line using Bresenham's algorithm, dashed for moves driven in reverse
+    fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, dashed: bool) {
         let dx = (x1 - x0).abs();
         let dy = -(y1 - y0).abs();
         let sx = if x0 < x1 { 1 } else { -1 };
@@ -50,7 +48,10 @@ impl Canvas {
 
         let (mut x, mut y) = (x0, y0);
         while x != x1 || y != y1 {
-            self.set_pixel(x as u32, y as u32);
+            if !dashed || self.dash_step % 6 < 3 {
+                self.set_pixel(x as u32, y as u32);
+            }
+            self.dash_step += 1;
             let e2 = 2 * err;
             if e2 >= dy {
                 err += dy;
@@ -71,6 +72,7 @@ impl Canvas {
         p2: Vec2<f64>,
         p3: Vec2<f64>,
         steps: usize,
+        dashed: bool,
     ) {
         let mut prev = (p0.x() as i32, p0.y() as i32);
         for i in 1..=steps {
@@ -85,7 +87,7 @@ impl Canvas {
                 + t.powi(3) * p3.y();
 
             let next = (x as i32, y as i32);
-            self.draw_line(prev.0, prev.1, next.0, next.1);
+            self.draw_line(prev.0, prev.1, next.0, next.1, dashed);
             prev = next;
         }
     }
@@ -96,15 +98,15 @@ impl Canvas {
 
         for command in commands {
             match command {
-                Command::Coordinate(coord) => {
+                Command::Coordinate(coord, reverse) => {
                     let current = (coord.x() as i32, coord.y() as i32);
                     if let Some(prev) = prev {
-                        self.draw_line(prev.0, prev.1, current.0, current.1);
+                        self.draw_line(prev.0, prev.1, current.0, current.1, *reverse);
                     }
                     prev = Some(current);
                 }
-                Command::CubicBezier(p0, p1, p2, p3) => {
-                    self.draw_bezier(*p0, *p1, *p2, *p3, 100);
+                Command::CubicBezier(p0, p1, p2, p3, reverse) => {
+                    self.draw_bezier(*p0, *p1, *p2, *p3, 100, *reverse);
                     prev = Some((p3.x() as i32, p3.y() as i32));
                 }
                 _ => (), // Ignore other commands
@@ -112,13 +114,30 @@ impl Canvas {
         }
     }
 
-    /// Converts the pixel buffer to an image format that Slint can display
-    pub fn to_image(&self) -> Image {
-        let mut pixel_buffer = SharedPixelBuffer::new(self.width, self.height);
+    /// Marks where the robot starts: a dot on its position with a line
+    /// showing which way it faces
+    pub fn draw_pose(&mut self, position: Vec2<f64>, heading: f64) {
+        let (x, y) = (position.x() as i32, position.y() as i32);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                self.set_pixel((x + dx) as u32, (y + dy) as u32);
+            }
+        }
+
+        let facing = position + Vec2::from_polar(6.0, heading.to_radians());
+        self.draw_line(x, y, facing.x() as i32, facing.y() as i32, false);
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
 
-        let buffer = pixel_buffer.make_mut_bytes();
-        buffer.copy_from_slice(&self.buffer);
+    pub fn height(&self) -> u32 {
+        self.height
+    }
 
-        Image::from_rgba8(pixel_buffer)
+    /// The drawn pixels, row by row in RGBA format
+    pub fn pixels(&self) -> &[u8] {
+        &self.buffer
     }
 }
@@ -1,10 +1,11 @@
 mod canvas;
 mod reverse;
 
-use alloc::rc::Rc;
+use alloc::{format, rc::Rc};
 use core::cell::RefCell;
 
 use canvas::Canvas;
+use slint::{Image, SharedPixelBuffer};
 use vexide::{
     io::println,
     prelude::{Display, spawn},
@@ -12,9 +13,8 @@ use vexide::{
 use vexide_slint::initialize_slint_platform;
 
 use crate::{
-    RobotSettings,
-    Color,
     autonomous::{PATHS, command},
+    config::{Color, RobotSettings, TRACK_WIDTH},
 };
 
 slint::include_modules!();
@@ -41,7 +41,12 @@ pub fn initialize_slint_gui(display: Display, settings: Rc<RefCell<RobotSettings
             let mut commands = match command::path_to_commands(PATHS[index]) {
                 Ok(commands) => commands,
                 Err(e) => {
-                    println!("Error parsing command '{}'", e);
+                    println!("Error parsing path: {}", e);
+
+                    // Show the error on the Brain screen so the broken line can be found
+                    if let Some(ui) = ui_handler.upgrade() {
+                        ui.set_parse_error(format!("{}", e).into());
+                    }
                     return; // Early return to avoid further execution on error
                 }
             };
@@ -57,15 +62,19 @@ pub fn initialize_slint_gui(display: Display, settings: Rc<RefCell<RobotSettings
             }
 
             // Convert commands into coordinates for visualization
-            let coords = command::command_to_coords(&commands);
+            let coords = command::command_to_coords(&commands, TRACK_WIDTH);
 
             // Create a canvas and draw the path
             let mut canvas = Canvas::new(144, 144, color);
             canvas.draw_commands(&coords);
+            if let Some(&command::Command::Pose(position, heading)) = commands.first() {
+                canvas.draw_pose(position, heading);
+            }
 
             // Ensure the UI handler is still valid before updating
             if let Some(ui) = ui_handler.upgrade() {
-                ui.set_path_image(canvas.to_image());
+                ui.set_path_image(to_image(&canvas));
+                ui.set_parse_error("".into());
             }
 
             // Update the robot settings with the selected autonomous path and color
@@ -92,3 +101,13 @@ pub fn initialize_slint_gui(display: Display, settings: Rc<RefCell<RobotSettings
     })
     .detach();
 }
+
+/// Converts a canvas's pixels to an image format that Slint can display
+fn to_image(canvas: &Canvas) -> Image {
+    let mut pixel_buffer = SharedPixelBuffer::new(canvas.width(), canvas.height());
+
+    let buffer = pixel_buffer.make_mut_bytes();
+    buffer.copy_from_slice(canvas.pixels());
+
+    Image::from_rgba8(pixel_buffer)
+}
@@ -1,8 +1,6 @@
 use alloc::vec::Vec;
 
-use evian::prelude::Vec2;
-
-use crate::autonomous::command::Command;
+use crate::{autonomous::command::Command, pose::Vec2};
 
 // Swap coordinates to match the blue side of the field
 pub fn reverse_coord(coord: &Vec2<f64>) -> Vec2<f64> {
@@ -24,15 +22,26 @@ pub fn invert_coords(commands: &[Command]) -> Vec<Command> {
     commands
         .iter()
         .map(|command| match command {
-            Command::TurnTo(angle) => Command::TurnTo(reverse_angle(*angle)),
-            Command::Coordinate(coord) => Command::Coordinate(reverse_coord(coord)),
-            Command::CubicBezier(p0, p1, p2, p3) => Command::CubicBezier(
+            Command::TurnTo(angle, profile) => Command::TurnTo(reverse_angle(*angle), *profile),
+            // Mirroring the field swaps which side the robot pivots on
+            Command::SwingTo(angle, side) => {
+                Command::SwingTo(reverse_angle(*angle), side.opposite())
+            }
+            Command::ArcTo(angle, radius) => Command::ArcTo(reverse_angle(*angle), *radius),
+            Command::Coordinate(coord, reverse) => {
+                Command::Coordinate(reverse_coord(coord), *reverse)
+            }
+            Command::CubicBezier(p0, p1, p2, p3, reverse) => Command::CubicBezier(
                 reverse_coord(p0),
                 reverse_coord(p1),
                 reverse_coord(p2),
                 reverse_coord(p3),
+                *reverse,
             ),
             Command::Pose(coord, angle) => Command::Pose(reverse_coord(coord), *angle),
+            Command::MoveToPose(coord, angle, lead, reverse) => {
+                Command::MoveToPose(reverse_coord(coord), reverse_angle(*angle), *lead, *reverse)
+            }
             _ => *command,
         })
         .collect()
@@ -0,0 +1,35 @@
+//! Measurements and settings shared by the robot program and the simulator
+
+use alloc::vec::Vec;
+
+use crate::autonomous::command::Command;
+
+pub const TRACK_WIDTH: f64 = 12.75;
+// Free speed of the blue drive cartridges (600 rpm) through the gearing
+pub const DRIVE_RPM: f64 = 600.0 * GEARING;
+pub const GEARING: f64 = 36.0 / 48.0;
+pub const WHEEL_DIAMETER: f64 = 3.25;
+// Highest voltage a V5 motor can be driven at
+pub const MAX_VOLTAGE: f64 = 12.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    Red,
+    Blue,
+}
+
+impl Color {
+    /// The color of the other alliance
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Red => Self::Blue,
+            Self::Blue => Self::Red,
+        }
+    }
+}
+
+pub struct RobotSettings {
+    pub auton_path: Vec<Command>,
+    pub test_auton: bool,
+    pub curr_color: Color,
+}
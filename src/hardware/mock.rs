@@ -0,0 +1,230 @@
+//! Stand-in devices for running the robot's logic on a computer. Readings are
+//! set by hand and outputs are recorded instead of moving anything.
+
+use alloc::rc::Rc;
+use core::cell::{RefCell, RefMut};
+
+use super::{
+    BrakeMode, DeviceError, Hardware, ImuDevice, MotorDevice, OpticalDevice, PneumaticDevice,
+    RotationDevice,
+};
+use crate::config::MAX_VOLTAGE;
+
+/// The mock devices, for building a robot to simulate or test
+pub struct Mocks;
+
+impl Hardware for Mocks {
+    type Motor = MockMotor;
+    type Rotation = MockRotation;
+    type Optical = MockOptical;
+    type Imu = MockImu;
+    type Pneumatic = MockPneumatic;
+}
+
+/// A mock device. Clones share the same state, so one can be handed to the
+/// code under test while another is kept to set readings and check outputs.
+#[derive(Clone, Debug, Default)]
+pub struct Mock<S>(Rc<RefCell<S>>);
+
+impl<S> Mock<S> {
+    pub fn new(state: S) -> Self {
+        Self(Rc::new(RefCell::new(state)))
+    }
+
+    /// The device's readings and the last outputs it was given
+    pub fn state(&self) -> RefMut<'_, S> {
+        self.0.borrow_mut()
+    }
+
+    /// Reads the state, failing like the real device would if it's unplugged
+    fn read<T>(&self, read: impl FnOnce(&S) -> T) -> Result<T, DeviceError>
+    where
+        S: Connection,
+    {
+        let state = self.0.borrow();
+        if state.disconnected() {
+            return Err(DeviceError);
+        }
+        Ok(read(&state))
+    }
+
+    /// Changes the state, failing like the real device would if it's unplugged
+    fn write(&self, write: impl FnOnce(&mut S)) -> Result<(), DeviceError>
+    where
+        S: Connection,
+    {
+        let mut state = self.0.borrow_mut();
+        if state.disconnected() {
+            return Err(DeviceError);
+        }
+        write(&mut state);
+        Ok(())
+    }
+}
+
+/// Device state that can be unplugged to test how the robot copes
+trait Connection {
+    fn disconnected(&self) -> bool;
+}
+
+macro_rules! connection {
+    ($($state:ty),*) => {
+        $(impl Connection for $state {
+            fn disconnected(&self) -> bool {
+                self.disconnected
+            }
+        })*
+    };
+}
+
+connection!(
+    MotorState,
+    RotationState,
+    OpticalState,
+    ImuState,
+    PneumaticState
+);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MotorState {
+    /// Voltage the motor was last driven at, zero once it's braking
+    pub voltage: f64,
+    /// How the motor was last told to stop, `None` while it's being driven
+    pub brake: Option<BrakeMode>,
+    pub max_voltage: f64,
+    /// Revolutions, set by the test or a simulation
+    pub position: f64,
+    /// Rpm, set by the test or a simulation
+    pub velocity: f64,
+    pub disconnected: bool,
+}
+
+impl Default for MotorState {
+    fn default() -> Self {
+        Self {
+            voltage: 0.0,
+            brake: Some(BrakeMode::Coast),
+            max_voltage: MAX_VOLTAGE,
+            position: 0.0,
+            velocity: 0.0,
+            disconnected: false,
+        }
+    }
+}
+
+pub type MockMotor = Mock<MotorState>;
+
+impl MotorDevice for MockMotor {
+    fn set_voltage(&mut self, voltage: f64) -> Result<(), DeviceError> {
+        self.write(|state| {
+            state.voltage = voltage.clamp(-state.max_voltage, state.max_voltage);
+            state.brake = None;
+        })
+    }
+
+    fn brake(&mut self, mode: BrakeMode) -> Result<(), DeviceError> {
+        self.write(|state| {
+            state.voltage = 0.0;
+            state.brake = Some(mode);
+        })
+    }
+
+    fn max_voltage(&self) -> f64 {
+        self.0.borrow().max_voltage
+    }
+
+    fn position(&self) -> Result<f64, DeviceError> {
+        self.read(|state| state.position)
+    }
+
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        self.write(|state| state.position = 0.0)
+    }
+
+    fn velocity(&self) -> Result<f64, DeviceError> {
+        self.read(|state| state.velocity)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RotationState {
+    /// Revolutions
+    pub position: f64,
+    pub disconnected: bool,
+}
+
+pub type MockRotation = Mock<RotationState>;
+
+impl RotationDevice for MockRotation {
+    fn position(&self) -> Result<f64, DeviceError> {
+        self.read(|state| state.position)
+    }
+
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        self.write(|state| state.position = 0.0)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpticalState {
+    /// Degrees
+    pub hue: f64,
+    pub proximity: f64,
+    pub disconnected: bool,
+}
+
+pub type MockOptical = Mock<OpticalState>;
+
+impl OpticalDevice for MockOptical {
+    fn hue(&self) -> Result<f64, DeviceError> {
+        self.read(|state| state.hue)
+    }
+
+    fn proximity(&self) -> Result<f64, DeviceError> {
+        self.read(|state| state.proximity)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImuState {
+    /// Degrees
+    pub heading: f64,
+    pub disconnected: bool,
+}
+
+pub type MockImu = Mock<ImuState>;
+
+impl ImuDevice for MockImu {
+    fn heading(&self) -> Result<f64, DeviceError> {
+        self.read(|state| state.heading)
+    }
+
+    async fn calibrate(&mut self) -> Result<(), DeviceError> {
+        self.read(|_| ())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PneumaticState {
+    pub extended: bool,
+    /// How many times the solenoid has switched
+    pub switches: usize,
+    pub disconnected: bool,
+}
+
+pub type MockPneumatic = Mock<PneumaticState>;
+
+impl PneumaticDevice for MockPneumatic {
+    fn is_extended(&self) -> Result<bool, DeviceError> {
+        self.read(|state| state.extended)
+    }
+
+    fn set_extended(&mut self, extended: bool) -> Result<(), DeviceError> {
+        self.write(|state| {
+            if state.extended != extended {
+                state.switches += 1;
+            }
+            state.extended = extended;
+        })
+    }
+}
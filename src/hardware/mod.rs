@@ -0,0 +1,96 @@
+//! The devices the robot's logic talks to, as traits. The vexide devices
+//! implement them on the Brain, and mocks stand in for them on a computer,
+//! where the simulator and tests drive them.
+
+use core::future::Future;
+
+#[cfg(not(target_vendor = "vex"))]
+pub mod mock;
+#[cfg(target_vendor = "vex")]
+pub mod vex;
+
+/// One kind of each device, so the robot can be built from either the real
+/// ones or the mocks
+pub trait Hardware: 'static {
+    type Motor: MotorDevice + 'static;
+    type Rotation: RotationDevice + 'static;
+    type Optical: OpticalDevice + 'static;
+    type Imu: ImuDevice + 'static;
+    type Pneumatic: PneumaticDevice + 'static;
+}
+
+/// A device couldn't be read or written, usually because it's unplugged
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeviceError;
+
+/// How a motor stops when it's no longer driven
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BrakeMode {
+    /// Spin down freely
+    Coast,
+
+    /// Stop quickly, but let the motor be pushed afterwards
+    Brake,
+
+    /// Stop and hold the motor where it is
+    Hold,
+}
+
+pub trait MotorDevice {
+    /// Drives the motor at a voltage, negative to spin backwards
+    fn set_voltage(&mut self, voltage: f64) -> Result<(), DeviceError>;
+
+    /// Stops driving the motor
+    fn brake(&mut self, mode: BrakeMode) -> Result<(), DeviceError>;
+
+    /// Highest voltage the motor can be driven at
+    fn max_voltage(&self) -> f64;
+
+    /// Revolutions the motor has turned since its position was last reset
+    fn position(&self) -> Result<f64, DeviceError>;
+
+    /// Sets the motor's current position to zero
+    fn reset_position(&mut self) -> Result<(), DeviceError>;
+
+    /// How fast the motor is spinning (rpm)
+    fn velocity(&self) -> Result<f64, DeviceError>;
+}
+
+pub trait RotationDevice {
+    /// Revolutions the sensor has turned since its position was last reset
+    fn position(&self) -> Result<f64, DeviceError>;
+
+    /// Sets the sensor's current position to zero
+    fn reset_position(&mut self) -> Result<(), DeviceError>;
+}
+
+pub trait OpticalDevice {
+    /// Hue of the color in front of the sensor (degrees, from 0 to 360)
+    fn hue(&self) -> Result<f64, DeviceError>;
+
+    /// How close an object is to the sensor, from 0.0 (nothing) to 1.0 (touching)
+    fn proximity(&self) -> Result<f64, DeviceError>;
+}
+
+pub trait ImuDevice {
+    /// Heading the robot is facing (degrees, clockwise from where it was calibrated)
+    fn heading(&self) -> Result<f64, DeviceError>;
+
+    /// Measures how the sensor drifts while the robot sits still. Readings
+    /// can't be trusted until it's done.
+    fn calibrate(&mut self) -> impl Future<Output = Result<(), DeviceError>>;
+}
+
+pub trait PneumaticDevice {
+    /// Checks if the solenoid is letting air through
+    fn is_extended(&self) -> Result<bool, DeviceError>;
+
+    /// Opens or closes the solenoid
+    fn set_extended(&mut self, extended: bool) -> Result<(), DeviceError>;
+
+    /// Switches the solenoid to the opposite state
+    fn toggle(&mut self) -> Result<(), DeviceError> {
+        let extended = self.is_extended()?;
+        self.set_extended(!extended)
+    }
+}
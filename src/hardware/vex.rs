@@ -0,0 +1,111 @@
+use vexide::{
+    devices::adi::digital::LogicLevel,
+    prelude::{
+        AdiDigitalOut, BrakeMode as VexideBrakeMode, InertialSensor, Motor, OpticalSensor,
+        RotationSensor,
+    },
+};
+
+use super::{
+    BrakeMode, DeviceError, Hardware, ImuDevice, MotorDevice, OpticalDevice, PneumaticDevice,
+    RotationDevice,
+};
+
+/// The devices plugged into the Brain
+pub struct Vex;
+
+impl Hardware for Vex {
+    type Motor = Motor;
+    type Rotation = RotationSensor;
+    type Optical = OpticalSensor;
+    type Imu = InertialSensor;
+    type Pneumatic = AdiDigitalOut;
+}
+
+impl MotorDevice for Motor {
+    fn set_voltage(&mut self, voltage: f64) -> Result<(), DeviceError> {
+        Motor::set_voltage(self, voltage).map_err(|_| DeviceError)
+    }
+
+    fn brake(&mut self, mode: BrakeMode) -> Result<(), DeviceError> {
+        let mode = match mode {
+            BrakeMode::Coast => VexideBrakeMode::Coast,
+            BrakeMode::Brake => VexideBrakeMode::Brake,
+            BrakeMode::Hold => VexideBrakeMode::Hold,
+        };
+        Motor::brake(self, mode).map_err(|_| DeviceError)
+    }
+
+    fn max_voltage(&self) -> f64 {
+        Motor::max_voltage(self)
+    }
+
+    fn position(&self) -> Result<f64, DeviceError> {
+        Motor::position(self)
+            .map(|position| position.as_revolutions())
+            .map_err(|_| DeviceError)
+    }
+
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        Motor::reset_position(self).map_err(|_| DeviceError)
+    }
+
+    fn velocity(&self) -> Result<f64, DeviceError> {
+        Motor::velocity(self).map_err(|_| DeviceError)
+    }
+}
+
+impl RotationDevice for RotationSensor {
+    fn position(&self) -> Result<f64, DeviceError> {
+        RotationSensor::position(self)
+            .map(|position| position.as_revolutions())
+            .map_err(|_| DeviceError)
+    }
+
+    fn reset_position(&mut self) -> Result<(), DeviceError> {
+        RotationSensor::reset_position(self).map_err(|_| DeviceError)
+    }
+}
+
+impl OpticalDevice for OpticalSensor {
+    fn hue(&self) -> Result<f64, DeviceError> {
+        OpticalSensor::hue(self).map_err(|_| DeviceError)
+    }
+
+    fn proximity(&self) -> Result<f64, DeviceError> {
+        OpticalSensor::proximity(self).map_err(|_| DeviceError)
+    }
+}
+
+impl ImuDevice for InertialSensor {
+    fn heading(&self) -> Result<f64, DeviceError> {
+        InertialSensor::heading(self).map_err(|_| DeviceError)
+    }
+
+    async fn calibrate(&mut self) -> Result<(), DeviceError> {
+        InertialSensor::calibrate(self)
+            .await
+            .map_err(|_| DeviceError)
+    }
+}
+
+impl PneumaticDevice for AdiDigitalOut {
+    fn is_extended(&self) -> Result<bool, DeviceError> {
+        self.level()
+            .map(|level| matches!(level, LogicLevel::High))
+            .map_err(|_| DeviceError)
+    }
+
+    fn set_extended(&mut self, extended: bool) -> Result<(), DeviceError> {
+        let level = if extended {
+            LogicLevel::High
+        } else {
+            LogicLevel::Low
+        };
+        self.set_level(level).map_err(|_| DeviceError)
+    }
+
+    fn toggle(&mut self) -> Result<(), DeviceError> {
+        AdiDigitalOut::toggle(self).map_err(|_| DeviceError)
+    }
+}
@@ -0,0 +1,108 @@
+use super::{CREEP_VELOCITY, Follower, wrap_angle};
+use crate::{platform::Float, pose::Vec2};
+
+/// Tuning for the boomerang controller
+#[derive(Clone, Copy, Debug)]
+pub struct BoomerangGains {
+    /// Forward speed (inches/second) per inch from the carrot point
+    pub linear: f64,
+    /// Turn rate (radians/second) per radian the robot is facing away from where it should
+    pub angular: f64,
+    /// Fraction of the distance to the goal the carrot point is led out by
+    pub lead: f64,
+}
+
+/// Within this distance of the goal (inches), the robot stops chasing the
+/// carrot and drives straight at the goal while turning to the final heading
+const SETTLE_RADIUS: f64 = 6.0;
+
+/// How far off the final heading still counts as arrived (radians)
+const ANGLE_TOLERANCE: f64 = 0.05;
+
+/// Drives to a pose by chasing a "carrot" point led out behind the goal along
+/// its final heading. The carrot slides in towards the goal as the robot gets
+/// closer, so the robot swings around and arrives already facing the right way.
+pub struct Boomerang {
+    target: Vec2<f64>,
+    /// Final heading (radians, counterclockwise from the x axis)
+    heading: f64,
+    gains: BoomerangGains,
+    max_velocity: f64,
+    track_width: f64,
+    /// Distance from the goal that counts as arrived (inches)
+    tolerance: f64,
+}
+
+impl Boomerang {
+    pub fn new(
+        target: Vec2<f64>,
+        heading: f64,
+        gains: BoomerangGains,
+        max_velocity: f64,
+        track_width: f64,
+        tolerance: f64,
+    ) -> Self {
+        Self {
+            target,
+            heading,
+            gains,
+            max_velocity,
+            track_width,
+            tolerance,
+        }
+    }
+
+    /// The point the robot is currently chasing
+    pub fn carrot(&self, position: Vec2<f64>) -> Vec2<f64> {
+        let distance = (self.target.x() - position.x()).hypot(self.target.y() - position.y());
+        let lead = self.gains.lead * distance;
+
+        Vec2::new(
+            self.target.x() - lead * self.heading.cos(),
+            self.target.y() - lead * self.heading.sin(),
+        )
+    }
+}
+
+impl Follower for Boomerang {
+    fn update(&mut self, position: Vec2<f64>, heading: f64, _dt: f64) -> Option<(f64, f64)> {
+        let distance = (self.target.x() - position.x()).hypot(self.target.y() - position.y());
+        let final_error = wrap_angle(self.heading - heading);
+        if distance < self.tolerance && final_error.abs() < ANGLE_TOLERANCE {
+            return None;
+        }
+
+        let carrot = self.carrot(position);
+        let (dx, dy) = (carrot.x() - position.x(), carrot.y() - position.y());
+        let facing_error = wrap_angle(dy.atan2(dx) - heading);
+
+        let (velocity, angular) = if distance < SETTLE_RADIUS {
+            // The carrot is almost on the goal by now, so close the rest of the
+            // distance along the way the robot faces, backing up if it overshot
+            let along = (self.target.x() - position.x()) * heading.cos()
+                + (self.target.y() - position.y()) * heading.sin();
+            let velocity = if distance < self.tolerance {
+                0.0
+            } else {
+                // Fast enough to keep rolling the last few inches
+                (self.gains.linear * along.abs())
+                    .clamp(CREEP_VELOCITY, self.max_velocity)
+                    .copysign(along)
+            };
+            (velocity, self.gains.angular * final_error)
+        } else {
+            // Drive less the further the carrot is off to the side, and back up
+            // if it ends up behind the robot
+            let velocity =
+                (self.gains.linear * dx.hypot(dy)).min(self.max_velocity) * facing_error.cos();
+            (velocity, self.gains.angular * facing_error)
+        };
+
+        // Keep the outside wheel under the speed cap
+        let turn = angular * self.track_width / 2.0;
+        let (left, right) = (velocity - turn, velocity + turn);
+        let scale = (self.max_velocity / left.abs().max(right.abs())).min(1.0);
+
+        Some((left * scale, right * scale))
+    }
+}
@@ -0,0 +1,96 @@
+use alloc::vec::Vec;
+
+use crate::{platform::Float, pose::Vec2};
+
+/// A cubic Bézier curve from `p0` to `p3`, pulled towards `p1` and `p2`
+#[derive(Clone, Copy, Debug)]
+pub struct CubicBezier {
+    pub p0: Vec2<f64>,
+    pub p1: Vec2<f64>,
+    pub p2: Vec2<f64>,
+    pub p3: Vec2<f64>,
+}
+
+impl CubicBezier {
+    pub const fn new(p0: Vec2<f64>, p1: Vec2<f64>, p2: Vec2<f64>, p3: Vec2<f64>) -> Self {
+        Self { p0, p1, p2, p3 }
+    }
+
+    /// The point on the curve at `t`, from 0.0 at the start to 1.0 at the end
+    pub fn point(&self, t: f64) -> Vec2<f64> {
+        let u = 1.0 - t;
+        let [a, b, c, d] = [
+            u.powi(3),
+            3.0 * u.powi(2) * t,
+            3.0 * u * t.powi(2),
+            t.powi(3),
+        ];
+
+        Vec2::new(
+            a * self.p0.x() + b * self.p1.x() + c * self.p2.x() + d * self.p3.x(),
+            a * self.p0.y() + b * self.p1.y() + c * self.p2.y() + d * self.p3.y(),
+        )
+    }
+
+    /// The direction and speed the curve is heading in at `t`
+    pub fn derivative(&self, t: f64) -> Vec2<f64> {
+        let u = 1.0 - t;
+        let [a, b, c] = [3.0 * u.powi(2), 6.0 * u * t, 3.0 * t.powi(2)];
+
+        Vec2::new(
+            a * (self.p1.x() - self.p0.x())
+                + b * (self.p2.x() - self.p1.x())
+                + c * (self.p3.x() - self.p2.x()),
+            a * (self.p1.y() - self.p0.y())
+                + b * (self.p2.y() - self.p1.y())
+                + c * (self.p3.y() - self.p2.y()),
+        )
+    }
+
+    /// How quickly the direction of the curve changes at `t`
+    pub fn second_derivative(&self, t: f64) -> Vec2<f64> {
+        let u = 1.0 - t;
+
+        Vec2::new(
+            6.0 * u * (self.p2.x() - 2.0 * self.p1.x() + self.p0.x())
+                + 6.0 * t * (self.p3.x() - 2.0 * self.p2.x() + self.p1.x()),
+            6.0 * u * (self.p2.y() - 2.0 * self.p1.y() + self.p0.y())
+                + 6.0 * t * (self.p3.y() - 2.0 * self.p2.y() + self.p1.y()),
+        )
+    }
+
+    /// Heading of the curve at `t` (radians, counterclockwise from the x axis)
+    pub fn heading(&self, t: f64) -> f64 {
+        let d = self.derivative(t);
+        d.y().atan2(d.x())
+    }
+
+    /// Signed curvature at `t` (1/inches), positive when the curve bends to the left
+    pub fn curvature(&self, t: f64) -> f64 {
+        let d = self.derivative(t);
+        let dd = self.second_derivative(t);
+        let speed = d.x().hypot(d.y());
+
+        if speed < f64::EPSILON {
+            return 0.0;
+        }
+        (d.x() * dd.y() - d.y() * dd.x()) / speed.powi(3)
+    }
+
+    /// Points evenly spaced in `t` along the curve, including both ends
+    pub fn sample(&self, steps: usize) -> Vec<Vec2<f64>> {
+        (0..=steps)
+            .map(|i| self.point(i as f64 / steps as f64))
+            .collect()
+    }
+
+    /// Approximates the length of the curve (inches) by summing short straight
+    /// segments along it
+    pub fn length(&self) -> f64 {
+        let points = self.sample(100);
+        points
+            .windows(2)
+            .map(|pair| (pair[1].x() - pair[0].x()).hypot(pair[1].y() - pair[0].y()))
+            .sum()
+    }
+}
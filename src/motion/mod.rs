@@ -0,0 +1,42 @@
+//! Path following for the drivetrain. These only do the math: each follower is
+//! fed the robot's position every update and hands back wheel speeds, and the
+//! executor takes care of reading the sensors and driving the motors.
+
+use core::f64::consts::PI;
+
+use crate::{platform::Float, pose::Vec2};
+
+pub mod boomerang;
+pub mod curve;
+pub mod pid;
+pub mod profile;
+pub mod pursuit;
+pub mod ramsete;
+pub mod trajectory;
+
+/// Slowest a follower will drive before reaching the end (inches/second)
+const CREEP_VELOCITY: f64 = 4.0;
+
+/// Steers the robot along a path, one update at a time
+pub trait Follower {
+    /// Works out the left and right wheel speeds (inches/second) for the robot's
+    /// current position and heading (radians). `dt` is the time since the last
+    /// update (seconds). Returns `None` once the robot has reached the end.
+    fn update(&mut self, position: Vec2<f64>, heading: f64, dt: f64) -> Option<(f64, f64)>;
+}
+
+/// Runs a follower with the robot driving backwards. The follower steers an
+/// imaginary robot facing the other way, whose left side is the real right side.
+pub struct Reversed<F>(pub F);
+
+impl<F: Follower> Follower for Reversed<F> {
+    fn update(&mut self, position: Vec2<f64>, heading: f64, dt: f64) -> Option<(f64, f64)> {
+        let (left, right) = self.0.update(position, wrap_angle(heading + PI), dt)?;
+        Some((-right, -left))
+    }
+}
+
+/// Wraps an angle (radians) into the range -π to π
+pub fn wrap_angle(angle: f64) -> f64 {
+    angle.sin().atan2(angle.cos())
+}
@@ -0,0 +1,113 @@
+use core::time::Duration;
+
+use crate::platform::Instant;
+
+/// A PID controller, turning how far off the robot is into a voltage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pid {
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    /// Error under which the integral builds up. Outside of it, or once the
+    /// error crosses zero, the integral starts over.
+    integration_range: Option<f64>,
+    output_limit: Option<f64>,
+    integral: f64,
+    prev_error: f64,
+}
+
+impl Pid {
+    pub const fn new(kp: f64, ki: f64, kd: f64, integration_range: Option<f64>) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integration_range,
+            output_limit: None,
+            integral: 0.0,
+            prev_error: 0.0,
+        }
+    }
+
+    /// Caps the output at a voltage either way
+    pub const fn set_output_limit(&mut self, limit: Option<f64>) {
+        self.output_limit = limit;
+    }
+
+    /// Works out the output for the current error, `dt` after the last update.
+    /// Headings should be wrapped first so the robot turns the short way.
+    pub fn update(&mut self, error: f64, dt: Duration) -> f64 {
+        let dt = dt.as_secs_f64();
+        if self
+            .integration_range
+            .is_none_or(|range| error.abs() < range)
+            && error.signum() == self.prev_error.signum()
+        {
+            self.integral += error * dt;
+        } else {
+            self.integral = 0.0;
+        }
+
+        let derivative = (error - self.prev_error) / dt;
+        self.prev_error = error;
+
+        let output = self.kp * error + self.ki * self.integral + self.kd * derivative;
+        match self.output_limit {
+            Some(limit) => output.clamp(-limit, limit),
+            None => output,
+        }
+    }
+}
+
+/// How close and how still the robot has to be, and for how long, for a
+/// motion to count as settled
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tolerances {
+    pub duration: Option<Duration>,
+    pub error: Option<f64>,
+    pub velocity: Option<f64>,
+    /// When the robot last came within tolerance
+    since: Option<Instant>,
+}
+
+impl Tolerances {
+    pub const fn new() -> Self {
+        Self {
+            duration: None,
+            error: None,
+            velocity: None,
+            since: None,
+        }
+    }
+
+    pub const fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub const fn error(mut self, error: f64) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    pub const fn velocity(mut self, velocity: f64) -> Self {
+        self.velocity = Some(velocity);
+        self
+    }
+
+    /// Checks if the robot has stayed within tolerance for long enough
+    pub fn check(&mut self, error: f64, velocity: f64) -> bool {
+        let within = self.error.is_none_or(|tolerance| error.abs() < tolerance)
+            && self
+                .velocity
+                .is_none_or(|tolerance| velocity.abs() < tolerance);
+        if !within {
+            self.since = None;
+            return false;
+        }
+
+        let since = *self.since.get_or_insert_with(Instant::now);
+        self.duration
+            .is_none_or(|duration| since.elapsed() > duration)
+    }
+}
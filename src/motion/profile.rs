@@ -0,0 +1,190 @@
+use alloc::vec::Vec;
+
+use crate::platform::Float;
+
+/// Limits on how a profiled motion speeds up and slows down. Units depend on
+/// what's being profiled: inches for drives, radians for turns.
+#[derive(Clone, Copy, Debug)]
+pub struct ProfileConstraints {
+    pub max_velocity: f64,
+    pub max_acceleration: f64,
+    /// Fastest the acceleration itself can change. With `None` the
+    /// acceleration switches on and off at once, giving a trapezoidal profile,
+    /// otherwise it ramps up and down for a smoother S-curve.
+    pub max_jerk: Option<f64>,
+}
+
+/// How hard to drive the motors to follow a profile
+#[derive(Clone, Copy, Debug)]
+pub struct ProfileGains {
+    /// Voltage needed to get moving at all
+    pub ks: f64,
+    /// Voltage per unit of velocity
+    pub kv: f64,
+    /// Voltage per unit of acceleration
+    pub ka: f64,
+    /// Voltage per unit the robot has fallen behind or gotten ahead of the profile
+    pub kp: f64,
+}
+
+impl ProfileGains {
+    /// Voltage to follow a setpoint, given how far the robot actually is
+    pub fn voltage(&self, setpoint: ProfileState, measured: f64) -> f64 {
+        let static_friction = if setpoint.velocity.abs() > f64::EPSILON {
+            self.ks * setpoint.velocity.signum()
+        } else {
+            0.0
+        };
+
+        static_friction
+            + self.kv * setpoint.velocity
+            + self.ka * setpoint.acceleration
+            + self.kp * (setpoint.position - measured)
+    }
+}
+
+/// Where a profile is at one moment
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProfileState {
+    pub position: f64,
+    pub velocity: f64,
+    pub acceleration: f64,
+}
+
+/// A stretch of the profile where the jerk stays the same
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    duration: f64,
+    jerk: f64,
+    /// Acceleration at the start of the segment
+    acceleration: f64,
+}
+
+/// Plans how to cover a distance from a stop to a stop as quickly as the
+/// constraints allow, then tells the robot where it should be at each moment
+#[derive(Clone, Debug)]
+pub struct MotionProfile {
+    segments: Vec<Segment>,
+    /// -1.0 to move backwards, 1.0 to move forwards
+    direction: f64,
+}
+
+impl MotionProfile {
+    pub fn new(distance: f64, constraints: ProfileConstraints) -> Self {
+        let ProfileConstraints {
+            max_velocity,
+            max_acceleration,
+            max_jerk,
+        } = constraints;
+        let length = distance.abs();
+
+        // Time to ramp the acceleration up or down, and the peak it reaches,
+        // while getting up to `velocity`
+        let ramp = |velocity: f64| match max_jerk {
+            Some(jerk) if velocity < max_acceleration.powi(2) / jerk => {
+                let time = (velocity / jerk).sqrt();
+                (time, jerk * time)
+            }
+            Some(jerk) => (max_acceleration / jerk, max_acceleration),
+            None => (0.0, max_acceleration),
+        };
+        // Distance covered getting up to `velocity`. Speeding up is symmetric
+        // about half that velocity, so it's the same as going half as fast throughout.
+        let speed_up = |velocity: f64| {
+            let (ramp_time, peak) = ramp(velocity);
+            let time = if peak > 0.0 {
+                velocity / peak + ramp_time
+            } else {
+                0.0
+            };
+            velocity * time / 2.0
+        };
+
+        // Go as fast as possible while still having room to stop
+        let mut peak_velocity = max_velocity;
+        if 2.0 * speed_up(max_velocity) > length {
+            let (mut low, mut high) = (0.0, max_velocity);
+            for _ in 0..50 {
+                let middle = (low + high) / 2.0;
+                if 2.0 * speed_up(middle) > length {
+                    high = middle;
+                } else {
+                    low = middle;
+                }
+            }
+            peak_velocity = low;
+        }
+
+        let (ramp_time, peak) = ramp(peak_velocity);
+        let hold_time = if peak > 0.0 {
+            (peak_velocity / peak - ramp_time).max(0.0)
+        } else {
+            0.0
+        };
+        let cruise_time = if peak_velocity > 0.0 {
+            ((length - 2.0 * speed_up(peak_velocity)) / peak_velocity).max(0.0)
+        } else {
+            0.0
+        };
+        let jerk = max_jerk.unwrap_or(0.0);
+
+        let segments = [
+            (ramp_time, jerk, 0.0),
+            (hold_time, 0.0, peak),
+            (ramp_time, -jerk, peak),
+            (cruise_time, 0.0, 0.0),
+            (ramp_time, -jerk, 0.0),
+            (hold_time, 0.0, -peak),
+            (ramp_time, jerk, -peak),
+        ]
+        .into_iter()
+        .filter(|&(duration, ..)| duration > 0.0)
+        .map(|(duration, jerk, acceleration)| Segment {
+            duration,
+            jerk,
+            acceleration,
+        })
+        .collect();
+
+        Self {
+            segments,
+            direction: distance.signum(),
+        }
+    }
+
+    /// How long the profile takes (seconds)
+    pub fn duration(&self) -> f64 {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+
+    /// Where the robot should be `time` seconds after the start
+    pub fn sample(&self, time: f64) -> ProfileState {
+        let mut state = ProfileState::default();
+        let mut remaining = time.max(0.0);
+
+        for segment in &self.segments {
+            let t = remaining.min(segment.duration);
+            let (a, j) = (segment.acceleration, segment.jerk);
+
+            state.position += state.velocity * t + a * t.powi(2) / 2.0 + j * t.powi(3) / 6.0;
+            state.velocity += a * t + j * t.powi(2) / 2.0;
+            state.acceleration = a + j * t;
+
+            remaining -= t;
+            if remaining <= 0.0 {
+                break;
+            }
+        }
+        // Once finished, the robot should be sitting still at the end
+        if remaining > 0.0 {
+            state.velocity = 0.0;
+            state.acceleration = 0.0;
+        }
+
+        ProfileState {
+            position: state.position * self.direction,
+            velocity: state.velocity * self.direction,
+            acceleration: state.acceleration * self.direction,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use alloc::vec::Vec;
+
+use super::{CREEP_VELOCITY, Follower, trajectory::Constraints};
+use crate::{platform::Float, pose::Vec2};
+
+/// Drives through a chain of waypoints without stopping at any of them by
+/// always steering towards a point a set distance further along the path
+pub struct PurePursuit {
+    waypoints: Vec<Vec2<f64>>,
+    /// How far ahead along the path to aim (inches)
+    lookahead: f64,
+    constraints: Constraints,
+    /// Distance from the last waypoint that counts as finished (inches)
+    tolerance: f64,
+    /// Index of the waypoint the current segment starts at
+    segment: usize,
+    velocity: f64,
+}
+
+impl PurePursuit {
+    pub fn new(
+        waypoints: Vec<Vec2<f64>>,
+        lookahead: f64,
+        constraints: Constraints,
+        tolerance: f64,
+    ) -> Self {
+        Self {
+            waypoints,
+            lookahead,
+            constraints,
+            tolerance,
+            segment: 0,
+            velocity: 0.0,
+        }
+    }
+
+    /// Total length of the path (inches)
+    pub fn length(&self) -> f64 {
+        self.waypoints
+            .windows(2)
+            .map(|pair| distance(pair[0], pair[1]))
+            .sum()
+    }
+
+    /// Finds the point to steer towards: the furthest point along the path
+    /// that is exactly `lookahead` away from the robot
+    fn lookahead_point(&mut self, position: Vec2<f64>) -> Vec2<f64> {
+        let mut target = None;
+        for i in self.segment..self.waypoints.len() - 1 {
+            let (start, end) = (self.waypoints[i], self.waypoints[i + 1]);
+            if let Some(t) = intersection(start, end, position, self.lookahead) {
+                self.segment = i;
+                target = Some(Vec2::new(
+                    start.x() + t * (end.x() - start.x()),
+                    start.y() + t * (end.y() - start.y()),
+                ));
+
+                // Once the path leaves the circle, later crossings would be
+                // the path looping back, not the way forward
+                if distance(end, position) > self.lookahead {
+                    break;
+                }
+            }
+        }
+
+        // Off the path, or close enough that the end is inside the circle:
+        // head straight for the next waypoint
+        target.unwrap_or(self.waypoints[(self.segment + 1).min(self.waypoints.len() - 1)])
+    }
+
+    /// Rough distance left to drive, through the waypoints still ahead
+    fn remaining(&self, position: Vec2<f64>) -> f64 {
+        let next = (self.segment + 1).min(self.waypoints.len() - 1);
+        distance(position, self.waypoints[next])
+            + self.waypoints[next..]
+                .windows(2)
+                .map(|pair| distance(pair[0], pair[1]))
+                .sum::<f64>()
+    }
+}
+
+impl Follower for PurePursuit {
+    fn update(&mut self, position: Vec2<f64>, heading: f64, dt: f64) -> Option<(f64, f64)> {
+        let end = *self.waypoints.last()?;
+        if distance(position, end) < self.tolerance {
+            return None;
+        }
+
+        // Finish if the robot has driven past the end of the last segment
+        if let [before, last] = self.waypoints[self.segment..] {
+            let past = (position.x() - last.x()) * (last.x() - before.x())
+                + (position.y() - last.y()) * (last.y() - before.y());
+            if past > 0.0 {
+                return None;
+            }
+        }
+
+        // Curvature of the arc from the robot to the lookahead point
+        let target = self.lookahead_point(position);
+        let (dx, dy) = (target.x() - position.x(), target.y() - position.y());
+        let sideways = -heading.sin() * dx + heading.cos() * dy;
+        let span = dx.hypot(dy);
+        let curvature = if span > f64::EPSILON {
+            2.0 * sideways / span.powi(2)
+        } else {
+            0.0
+        };
+
+        // Speed up gradually and slow down in time to stop at the end
+        let Constraints {
+            max_velocity,
+            max_acceleration,
+            track_width,
+            ..
+        } = self.constraints;
+        let stopping = (2.0 * max_acceleration * self.remaining(position)).sqrt();
+        self.velocity = (self.velocity + max_acceleration * dt)
+            .min(max_velocity)
+            .min(stopping);
+        let velocity = self.velocity.max(CREEP_VELOCITY);
+
+        // Keep the outside wheel under the speed cap on tight arcs
+        let turn = velocity * curvature * track_width / 2.0;
+        let (left, right) = (velocity - turn, velocity + turn);
+        let scale = (max_velocity / left.abs().max(right.abs())).min(1.0);
+
+        Some((left * scale, right * scale))
+    }
+}
+
+fn distance(a: Vec2<f64>, b: Vec2<f64>) -> f64 {
+    (b.x() - a.x()).hypot(b.y() - a.y())
+}
+
+/// Where along the segment from `start` to `end` (0.0 to 1.0) it leaves a
+/// circle of `radius` around `center`, if it does
+fn intersection(start: Vec2<f64>, end: Vec2<f64>, center: Vec2<f64>, radius: f64) -> Option<f64> {
+    let (dx, dy) = (end.x() - start.x(), end.y() - start.y());
+    let (fx, fy) = (start.x() - center.x(), start.y() - center.y());
+
+    let a = dx * dx + dy * dy;
+    let b = 2.0 * (fx * dx + fy * dy);
+    let c = fx * fx + fy * fy - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if a < f64::EPSILON || discriminant < 0.0 {
+        return None;
+    }
+
+    let t = (-b + discriminant.sqrt()) / (2.0 * a);
+    (0.0..=1.0).contains(&t).then_some(t)
+}
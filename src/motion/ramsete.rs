@@ -0,0 +1,110 @@
+use super::{CREEP_VELOCITY, Follower, trajectory::Trajectory, wrap_angle};
+use crate::{platform::Float, pose::Vec2};
+
+/// Tuning for the RAMSETE controller
+#[derive(Clone, Copy, Debug)]
+pub struct RamseteGains {
+    /// How aggressively to correct errors (1/inches²), like a proportional gain
+    pub b: f64,
+    /// How much to damp the correction (0.0 to 1.0)
+    pub zeta: f64,
+}
+
+/// How long the robot keeps correcting towards the end of the trajectory once
+/// it's run out of time (seconds)
+const SETTLE_TIME: f64 = 0.5;
+
+/// While settling, forward speed (inches/second) per inch from the end, and
+/// turn rate (radians/second) per radian off the end's heading
+const SETTLE_LINEAR: f64 = 12.0;
+const SETTLE_ANGULAR: f64 = 6.0;
+
+/// Drives along a trajectory on a clock. At each update the robot is steered
+/// towards where the trajectory says it should be by now, so a path takes the
+/// same amount of time every run. If the robot hasn't made it to the end when
+/// time's up, it gets a short while longer to close the gap.
+pub struct Ramsete<'a> {
+    trajectory: &'a Trajectory,
+    gains: RamseteGains,
+    track_width: f64,
+    /// Distance from the end that counts as arrived once time's up (inches)
+    tolerance: f64,
+    /// Time since the robot started along the trajectory (seconds)
+    time: f64,
+}
+
+impl<'a> Ramsete<'a> {
+    pub fn new(
+        trajectory: &'a Trajectory,
+        gains: RamseteGains,
+        track_width: f64,
+        tolerance: f64,
+    ) -> Self {
+        Self {
+            trajectory,
+            gains,
+            track_width,
+            tolerance,
+            time: 0.0,
+        }
+    }
+
+    /// Drives the rest of the way to the end along the way the robot faces,
+    /// backing up if it overshot, while turning to the end's heading
+    fn settle(&self, position: Vec2<f64>, heading: f64) -> Option<(f64, f64)> {
+        let end = self.trajectory.points.last()?;
+        let dx = end.position.x() - position.x();
+        let dy = end.position.y() - position.y();
+        if dx.hypot(dy) < self.tolerance {
+            return None;
+        }
+
+        let along = heading.cos() * dx + heading.sin() * dy;
+        let velocity = (SETTLE_LINEAR * along.abs())
+            .max(CREEP_VELOCITY)
+            .copysign(along);
+        let angular = SETTLE_ANGULAR * wrap_angle(end.heading - heading);
+
+        let turn = angular * self.track_width / 2.0;
+        Some((velocity - turn, velocity + turn))
+    }
+}
+
+impl Follower for Ramsete<'_> {
+    fn update(&mut self, position: Vec2<f64>, heading: f64, dt: f64) -> Option<(f64, f64)> {
+        self.time += dt;
+        let duration = self.trajectory.duration();
+        if self.trajectory.points.is_empty() || self.time > duration + SETTLE_TIME {
+            return None;
+        }
+        if self.time > duration {
+            return self.settle(position, heading);
+        }
+
+        let target = self.trajectory.sample(self.time);
+        let velocity = target.velocity;
+        let angular = velocity * target.curvature;
+
+        // Error in the robot's own frame: `forward` is ahead of it, `left` is to its left
+        let dx = target.position.x() - position.x();
+        let dy = target.position.y() - position.y();
+        let forward = heading.cos() * dx + heading.sin() * dy;
+        let left = -heading.sin() * dx + heading.cos() * dy;
+        let turned = wrap_angle(target.heading - heading);
+
+        // sin(x)/x, which heads to 1 as x gets small
+        let sinc = if turned.abs() < 1e-6 {
+            1.0
+        } else {
+            turned.sin() / turned
+        };
+
+        let RamseteGains { b, zeta } = self.gains;
+        let k = 2.0 * zeta * (angular.powi(2) + b * velocity.powi(2)).sqrt();
+        let velocity = velocity * turned.cos() + k * forward;
+        let angular = angular + k * turned + b * target.velocity * sinc * left;
+
+        let turn = angular * self.track_width / 2.0;
+        Some((velocity - turn, velocity + turn))
+    }
+}
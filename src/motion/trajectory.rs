@@ -0,0 +1,146 @@
+use alloc::vec::Vec;
+
+use super::{curve::CubicBezier, wrap_angle};
+use crate::{platform::Float, pose::Vec2};
+
+/// Limits on how fast the robot can drive along a path
+#[derive(Clone, Copy, Debug)]
+pub struct Constraints {
+    /// Top speed of either side of the drivetrain (inches/second)
+    pub max_velocity: f64,
+    /// Fastest the robot can speed up or slow down (inches/second²)
+    pub max_acceleration: f64,
+    /// Fastest the robot can be pulled sideways on a turn before it slides (inches/second²)
+    pub max_centripetal_acceleration: f64,
+    /// Distance between the left and right wheels (inches)
+    pub track_width: f64,
+}
+
+/// A single point along a trajectory
+#[derive(Clone, Copy, Debug)]
+pub struct TrajectoryPoint {
+    pub position: Vec2<f64>,
+    /// Direction of travel (radians, counterclockwise from the x axis)
+    pub heading: f64,
+    /// Signed curvature (1/inches), positive when the path bends to the left
+    pub curvature: f64,
+    /// Distance along the path from the start (inches)
+    pub distance: f64,
+    /// Speed the robot should be going at this point (inches/second)
+    pub velocity: f64,
+    /// When the robot should reach this point (seconds from the start)
+    pub time: f64,
+}
+
+/// A path broken into closely spaced points, each with the speed the robot
+/// should be going when it gets there and when it should get there
+#[derive(Clone, Debug)]
+pub struct Trajectory {
+    pub points: Vec<TrajectoryPoint>,
+}
+
+impl Trajectory {
+    /// Samples a curve about every `spacing` inches and works out how fast the
+    /// robot can go at each point. Tight turns slow it down so the outside wheel
+    /// stays under the top speed and the robot doesn't slide, and it speeds up
+    /// from and slows down to a stop no harder than it can.
+    pub fn generate(curve: &CubicBezier, spacing: f64, constraints: Constraints) -> Self {
+        let steps = ((curve.length() / spacing).ceil() as usize).max(1);
+
+        let mut points: Vec<TrajectoryPoint> = Vec::with_capacity(steps + 1);
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            let position = curve.point(t);
+            let curvature = curve.curvature(t);
+            let distance = points.last().map_or(0.0, |prev| {
+                prev.distance
+                    + (position.x() - prev.position.x()).hypot(position.y() - prev.position.y())
+            });
+
+            let wheel_limit =
+                constraints.max_velocity / (1.0 + curvature.abs() * constraints.track_width / 2.0);
+            let centripetal_limit = if curvature.abs() > f64::EPSILON {
+                (constraints.max_centripetal_acceleration / curvature.abs()).sqrt()
+            } else {
+                f64::INFINITY
+            };
+
+            points.push(TrajectoryPoint {
+                position,
+                heading: curve.heading(t),
+                curvature,
+                distance,
+                velocity: wheel_limit.min(centripetal_limit),
+                time: 0.0,
+            });
+        }
+
+        // Start and end at a stop, then work forwards and backwards so the
+        // robot never has to speed up or brake harder than it can
+        let last = points.len() - 1;
+        points[0].velocity = 0.0;
+        points[last].velocity = 0.0;
+        for i in 1..points.len() {
+            let gap = points[i].distance - points[i - 1].distance;
+            let reachable =
+                (points[i - 1].velocity.powi(2) + 2.0 * constraints.max_acceleration * gap).sqrt();
+            points[i].velocity = points[i].velocity.min(reachable);
+        }
+        for i in (0..last).rev() {
+            let gap = points[i + 1].distance - points[i].distance;
+            let reachable =
+                (points[i + 1].velocity.powi(2) + 2.0 * constraints.max_acceleration * gap).sqrt();
+            points[i].velocity = points[i].velocity.min(reachable);
+        }
+
+        // Speed changes evenly between points, so each gap takes its length
+        // over the average of the speeds at either end
+        for i in 1..points.len() {
+            let gap = points[i].distance - points[i - 1].distance;
+            let average = (points[i].velocity + points[i - 1].velocity) / 2.0;
+            let duration = if average > f64::EPSILON {
+                gap / average
+            } else {
+                0.0
+            };
+            points[i].time = points[i - 1].time + duration;
+        }
+
+        Self { points }
+    }
+
+    /// How long the trajectory takes to drive (seconds)
+    pub fn duration(&self) -> f64 {
+        self.points.last().map_or(0.0, |point| point.time)
+    }
+
+    /// Where the robot should be, and how it should be moving, `time` seconds
+    /// after the start. Blends between the two points on either side.
+    pub fn sample(&self, time: f64) -> TrajectoryPoint {
+        let next = self
+            .points
+            .partition_point(|point| point.time < time)
+            .clamp(1, self.points.len() - 1);
+        let (a, b) = (self.points[next - 1], self.points[next]);
+
+        let span = b.time - a.time;
+        let f = if span > f64::EPSILON {
+            ((time - a.time) / span).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let lerp = |from: f64, to: f64| from + (to - from) * f;
+
+        TrajectoryPoint {
+            position: Vec2::new(
+                lerp(a.position.x(), b.position.x()),
+                lerp(a.position.y(), b.position.y()),
+            ),
+            heading: a.heading + wrap_angle(b.heading - a.heading) * f,
+            curvature: lerp(a.curvature, b.curvature),
+            distance: lerp(a.distance, b.distance),
+            velocity: lerp(a.velocity, b.velocity),
+            time: lerp(a.time, b.time),
+        }
+    }
+}
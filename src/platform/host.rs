@@ -0,0 +1,184 @@
+//! A single-threaded executor on a simulated clock. Every tick, the main future
+//! and every spawned task are polled once, then the simulation steps forward.
+//! Nothing sleeps for real, so a whole routine runs as fast as it can be
+//! computed. Each thread has its own clock, so tests can run side by side.
+
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+use core::{
+    cell::{Cell, RefCell},
+    fmt,
+    future::Future,
+    ops::{Add, AddAssign, Sub},
+    pin::{Pin, pin},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+pub use std::println;
+
+/// `std` already has float math on a computer, so there's nothing to bring in
+pub trait Float {}
+
+/// How far the clock moves between polls
+pub const TICK: Duration = Duration::from_millis(1);
+
+type Slot = Rc<RefCell<Option<Pin<Box<dyn Future<Output = ()>>>>>>;
+
+std::thread_local! {
+    static NOW: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+    static TASKS: RefCell<Vec<Slot>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs a future to completion on the simulated clock. `step` is called after
+/// every tick with the tick's length, which is where a simulation moves the
+/// robot. Gives up once `limit` of simulated time has passed.
+pub fn run<T>(
+    future: impl Future<Output = T>,
+    limit: Duration,
+    mut step: impl FnMut(Duration),
+) -> Option<T> {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    let end = Instant::now() + limit;
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Some(output);
+        }
+        poll_tasks(&mut cx);
+
+        if Instant::now() >= end {
+            return None;
+        }
+        step(TICK);
+        NOW.with(|clock| clock.set(clock.get() + TICK));
+    }
+}
+
+/// Polls every spawned task once, including any spawned along the way
+fn poll_tasks(cx: &mut Context<'_>) {
+    let tasks: Vec<Slot> = TASKS.with(|tasks| tasks.borrow().clone());
+
+    for slot in tasks {
+        // Taken out while polling, so the task can spawn or drop others
+        let Some(mut task) = slot.borrow_mut().take() else {
+            continue;
+        };
+        // The task list and this loop hold the slot, and so does the task's
+        // handle unless it was dropped mid-poll
+        if task.as_mut().poll(cx).is_pending() && Rc::strong_count(&slot) > 2 {
+            *slot.borrow_mut() = Some(task);
+        }
+    }
+
+    // Finished and cancelled tasks are left empty
+    TASKS.with(|tasks| tasks.borrow_mut().retain(|slot| slot.borrow().is_some()));
+}
+
+/// A spawned task. Dropping it cancels the task.
+pub struct Task<T> {
+    slot: Slot,
+    output: Rc<Cell<Option<T>>>,
+}
+
+impl<T> fmt::Debug for Task<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Task").finish_non_exhaustive()
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.slot.borrow_mut().take();
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<T> {
+        match self.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Starts running a future alongside the main one
+pub fn spawn<T: 'static>(future: impl Future<Output = T> + 'static) -> Task<T> {
+    let output = Rc::new(Cell::new(None));
+    let result = output.clone();
+    let slot: Slot = Rc::new(RefCell::new(Some(Box::pin(async move {
+        result.set(Some(future.await));
+    }))));
+
+    TASKS.with(|tasks| tasks.borrow_mut().push(slot.clone()));
+    Task { slot, output }
+}
+
+/// A point in simulated time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    pub fn now() -> Self {
+        Self(NOW.with(Cell::get))
+    }
+
+    /// Simulated time passed since this instant
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        Self(self.0 + rhs)
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        self.0 += rhs;
+    }
+}
+
+impl Sub for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Self) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// Waits until the simulated clock reaches a point in time
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep { deadline }
+}
+
+/// Waits for a length of simulated time
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(Instant::now() + duration)
+}
+
+/// Future returned by [`sleep`] and [`sleep_until`]
+pub struct Sleep {
+    deadline: Instant,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//! What the robot's code needs from whatever it's running on: tasks, time,
+//! float math and printing. On the Brain these come from vexide. On a computer
+//! they run on a simulated clock, so the simulator and tests can run the
+//! robot's logic without a Brain.
+
+#[cfg(not(target_vendor = "vex"))]
+mod host;
+
+#[cfg(not(target_vendor = "vex"))]
+pub use host::{Float, Instant, TICK, Task, println, run, sleep, spawn};
+#[cfg(target_vendor = "vex")]
+pub use vexide::{
+    float::Float,
+    io::println,
+    task::{Task, spawn},
+    time::{Instant, sleep},
+};
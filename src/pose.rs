@@ -1,4 +1,52 @@
-use evian::math::Vec2;
+use core::ops::{Add, AddAssign};
+
+use crate::platform::Float;
+
+/// A point or offset on the field (inches)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2<T> {
+    x: T,
+    y: T,
+}
+
+impl Vec2<f64> {
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// The point `r` away from the origin in the direction `theta` (radians,
+    /// counterclockwise from the x axis)
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Self::new(r * theta.cos(), r * theta.sin())
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    /// Direction from the origin to the point (radians, counterclockwise from the x axis)
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+}
+
+impl Add for Vec2<f64> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Vec2<f64> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
 
 #[derive(Copy, Clone)]
 pub struct Pose {
@@ -0,0 +1,101 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{
+    autonomous::{
+        command::Command,
+        execute::{MotionSettings, execute_path},
+        report::Report,
+    },
+    config::RobotSettings,
+    hardware::{Hardware, ImuDevice},
+    platform::println,
+    subsystems::{
+        drivetrain::{DriveMotors, Drivetrain},
+        intake::Intake,
+        lady_brown::LadyBrown,
+    },
+};
+
+/// Everything plugged into the robot, either the real devices on their ports
+/// or mocks for the simulator and tests
+pub struct Devices<H: Hardware> {
+    pub imu: H::Imu,
+    pub left_motors: [H::Motor; 3],
+    pub right_motors: [H::Motor; 3],
+    pub intake_motor: H::Motor,
+    pub optical_sensor: H::Optical,
+    pub doinker: H::Pneumatic,
+    pub lady_brown_motor: H::Motor,
+    pub lady_brown_rotation: H::Rotation,
+    pub clamp: (H::Pneumatic, H::Pneumatic),
+}
+
+pub struct Robot<H: Hardware> {
+    pub drivetrain: Drivetrain<H::Motor>,
+    pub intake: Intake,
+    pub doinker: H::Pneumatic,
+    pub lady_brown: LadyBrown,
+    pub clamp: (H::Pneumatic, H::Pneumatic),
+    // Handles to the drive motors for measuring progress while the drivetrain is busy
+    pub drive_motors: DriveMotors<H::Motor>,
+
+    pub settings: Rc<RefCell<RobotSettings>>,
+}
+
+impl<H: Hardware> Robot<H> {
+    /// Puts the robot together from its devices. The simulator builds its
+    /// robot here too, so it's set up the same as the real one.
+    pub async fn new(devices: Devices<H>, settings: Rc<RefCell<RobotSettings>>) -> Self {
+        let Devices {
+            mut imu,
+            left_motors,
+            right_motors,
+            intake_motor,
+            optical_sensor,
+            doinker,
+            lady_brown_motor,
+            lady_brown_rotation,
+            clamp,
+        } = devices;
+
+        match imu.calibrate().await {
+            Ok(_) => println!("Calibration Successful"),
+            Err(e) => println!("Error {:?}", e),
+        }
+
+        let drivetrain = Drivetrain::new(left_motors, right_motors, imu);
+
+        Self {
+            drive_motors: drivetrain.motors.clone(),
+            drivetrain,
+            intake: Intake::new([intake_motor], optical_sensor, settings.clone(), 6.0, 14.0),
+            doinker,
+            lady_brown: LadyBrown::new([lady_brown_motor], lady_brown_rotation, None),
+            clamp,
+            settings,
+        }
+    }
+
+    /// Runs the selected routine from its starting pose, adding how each motion
+    /// went to `report`
+    pub async fn run_auton(&mut self, report: &mut Report) {
+        let auton_path: Vec<Command> = self.settings.borrow().auton_path.clone();
+
+        // Check if the path is empty
+        let Some(pose) = auton_path.first() else {
+            return;
+        };
+
+        // First element is starting position
+        if let Command::Pose(position, angle) = pose {
+            self.drivetrain.tracking.set_position(*position);
+            self.drivetrain.tracking.set_heading(angle.to_radians());
+        }
+
+        // Every run starts at full speed until the path says otherwise
+        let mut settings = MotionSettings::default();
+
+        execute_path(self, &auton_path, &mut settings, report).await;
+    }
+}
@@ -0,0 +1,28 @@
+//! Commands a path or the controller can give the mechanisms. Kept apart from
+//! the subsystems themselves so the build script can check paths with them.
+
+#[derive(Copy, Clone, Debug)]
+pub enum IntakeCommand {
+    Off,
+    On,
+    Voltage(f64),
+}
+
+impl core::ops::Not for IntakeCommand {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Off => Self::On,
+            Self::On => Self::Off,
+            Self::Voltage(_) => Self::Off,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LadyBrownCommand {
+    Next,
+    Angle(f64),
+    Voltage(f64),
+}
@@ -1,52 +1,202 @@
-use vexide::prelude::{Float, Motor};
+use alloc::rc::Rc;
+use core::{
+    cell::RefCell,
+    f64::consts::{PI, TAU},
+    time::Duration,
+};
 
-use crate::mappings::DriveMode;
+use crate::{
+    config::{GEARING, TRACK_WIDTH, WHEEL_DIAMETER},
+    hardware::{DeviceError, ImuDevice, MotorDevice},
+    motion::wrap_angle,
+    platform::{Float, Instant, Task, sleep, spawn},
+    pose::Vec2,
+};
 
-/// Applies an acceleration function to the given power value.
-/// Uses polynomial scaling based on the acceleration factor.
-fn get_acceleration(power: f64, acceleration: i32) -> f64 {
-    if acceleration == 1 {
-        return power; // If acceleration is 1, return power as is (linear mapping)
+/// The left and right drive motors, shared between the drivetrain, its
+/// tracking and anything measuring them while a motion holds the drivetrain
+pub type DriveMotors<M> = [Rc<RefCell<[M; 3]>>; 2];
+
+/// How often the tracking reads the sensors
+const TRACKING_INTERVAL: Duration = Duration::from_millis(5);
+
+pub struct Drivetrain<M> {
+    pub motors: DriveMotors<M>,
+    pub tracking: Tracking,
+}
+
+impl<M: MotorDevice + 'static> Drivetrain<M> {
+    /// Drives with the left and right motors, tracking where they take the
+    /// robot along with the inertial sensor
+    pub fn new(left: [M; 3], right: [M; 3], imu: impl ImuDevice + 'static) -> Self {
+        let motors = [Rc::new(RefCell::new(left)), Rc::new(RefCell::new(right))];
+
+        Self {
+            tracking: Tracking::new(motors.clone(), imu),
+            motors,
+        }
     }
+}
 
-    // Polynomial acceleration adjustment
-    power.powi(acceleration - 1)
-        * if acceleration % 2 == 0 {
-            power.abs() // Even acceleration preserves absolute magnitude
-        } else {
-            power // Odd acceleration preserves sign
+impl<M: MotorDevice> Drivetrain<M> {
+    /// Drives the left and right motors at their own voltages. Every motor is
+    /// set even if one of them fails.
+    pub fn set_voltages(&mut self, (left, right): (f64, f64)) -> Result<(), DeviceError> {
+        let mut result = Ok(());
+        for (side, voltage) in self.motors.iter().zip([left, right]) {
+            for motor in side.borrow_mut().iter_mut() {
+                if let Err(error) = motor.set_voltage(voltage) {
+                    result = Err(error);
+                }
+            }
         }
+        result
+    }
 }
 
-/// Computes the left and right motor power values based on the selected drive mode.
-/// Supports both Arcade and Tank drive configurations.
-pub fn differential_drive(drive_mode: &DriveMode) -> (f64, f64) {
-    let mut power_val = 0.0;
-    let mut turn_val = 0.0;
-    let mut left_val = 0.0;
-    let mut right_val = 0.0;
+#[derive(Clone, Copy, Debug, Default)]
+struct TrackingData {
+    position: Vec2<f64>,
+    /// How far the robot has turned since tracking started (radians,
+    /// counterclockwise), before `heading_offset` lines it up with the field
+    raw_heading: f64,
+    heading_offset: f64,
+    forward_travel: f64,
+    /// Inches/second
+    linear_velocity: f64,
+    /// Radians/second, counterclockwise
+    angular_velocity: f64,
+}
 
-    // Extract joystick values based on the configured drive mode
-    match drive_mode {
-        DriveMode::Arcade { power, turn } => {
-            power_val = power.y(); // Forward/backward movement
-            turn_val = turn.x(); // Turning movement
-        }
-        DriveMode::Tank { left, right } => {
-            left_val = left.y(); // Left side control
-            right_val = right.y(); // Right side control
+/// Keeps track of where the robot is from how far the drive wheels have rolled
+/// and how far the inertial sensor says it's turned, updated in the background
+pub struct Tracking {
+    data: Rc<RefCell<TrackingData>>,
+    _task: Task<()>,
+}
+
+impl Tracking {
+    pub fn new<M: MotorDevice + 'static>(
+        motors: DriveMotors<M>,
+        imu: impl ImuDevice + 'static,
+    ) -> Self {
+        let data = Rc::new(RefCell::new(TrackingData::default()));
+
+        Self {
+            data: data.clone(),
+            _task: spawn(async move {
+                // Once the inertial sensor drops out it recalibrates when it comes
+                // back, so the wheels take over turning for good
+                let mut imu = Some(imu);
+                let mut prev_imu_heading = imu.as_ref().and_then(|imu| imu.heading().ok());
+                let mut prev_travel = side_travel(&motors);
+                let mut prev_time = Instant::now();
+
+                loop {
+                    sleep(TRACKING_INTERVAL).await;
+                    let mut data = data.borrow_mut();
+
+                    let travel = side_travel(&motors);
+                    let moved = [0, 1].map(|i| Some(travel[i]? - prev_travel[i]?));
+                    prev_travel = travel;
+
+                    // The sensor reads clockwise degrees, and the field is counterclockwise
+                    let delta_heading = match imu.as_ref().map(|imu| imu.heading()) {
+                        Some(Ok(heading)) => {
+                            let turned = prev_imu_heading.map_or(0.0, |prev| prev - heading);
+                            prev_imu_heading = Some(heading);
+                            wrap_angle(turned.to_radians())
+                        }
+                        _ => {
+                            imu = None;
+                            match moved {
+                                [Some(left), Some(right)] => (right - left) / TRACK_WIDTH,
+                                // Nothing to tell which way the robot turned until they're back
+                                _ => 0.0,
+                            }
+                        }
+                    };
+
+                    // The sides are half the track width either side of the center,
+                    // so the center rolls as far as their average and turning in
+                    // place doesn't move it. Counting both sides a whole track
+                    // width out instead moved the tracked position 18 inches over
+                    // a 90° turn in the simulator.
+                    let moved = average(moved).unwrap_or_default();
+                    if let Some(forward) = average(travel) {
+                        data.forward_travel = forward;
+                    }
+
+                    // Driving in an arc, the robot moves along the chord between
+                    // where it started and ended, halfway through the turn
+                    let heading = data.raw_heading + data.heading_offset;
+                    let chord = if delta_heading == 0.0 {
+                        moved
+                    } else {
+                        2.0 * (delta_heading / 2.0).sin() * moved / delta_heading
+                    };
+                    data.position += Vec2::from_polar(chord, heading + delta_heading / 2.0);
+                    data.raw_heading += delta_heading;
+
+                    let dt = prev_time.elapsed().as_secs_f64();
+                    prev_time = Instant::now();
+                    data.linear_velocity = moved / dt;
+                    data.angular_velocity = delta_heading / dt;
+                }
+            }),
         }
     }
 
-    // Apply acceleration function if using Arcade drive
-    if !matches!(drive_mode, DriveMode::Tank { .. }) {
-        left_val = get_acceleration(power_val + turn_val, 1);
-        right_val = get_acceleration(power_val - turn_val, 1);
+    /// Where the robot is on the field (inches)
+    pub fn position(&self) -> Vec2<f64> {
+        self.data.borrow().position
     }
 
-    // Scale the final voltage values to the V5 motor's maximum voltage
-    (
-        left_val * Motor::V5_MAX_VOLTAGE,
-        right_val * Motor::V5_MAX_VOLTAGE,
-    )
+    /// Which way the robot is facing (radians, counterclockwise from the x axis,
+    /// from 0 to 2π)
+    pub fn heading(&self) -> f64 {
+        let data = self.data.borrow();
+        (data.raw_heading + data.heading_offset).rem_euclid(TAU)
+    }
+
+    /// How far the drive wheels have rolled forwards on average (inches)
+    pub fn forward_travel(&self) -> f64 {
+        self.data.borrow().forward_travel
+    }
+
+    /// How fast the robot is driving forwards (inches/second)
+    pub fn linear_velocity(&self) -> f64 {
+        self.data.borrow().linear_velocity
+    }
+
+    /// How fast the robot is turning counterclockwise (radians/second)
+    pub fn angular_velocity(&self) -> f64 {
+        self.data.borrow().angular_velocity
+    }
+
+    pub fn set_position(&mut self, position: Vec2<f64>) {
+        self.data.borrow_mut().position = position;
+    }
+
+    /// Sets which way the robot is facing now (radians, counterclockwise)
+    pub fn set_heading(&mut self, heading: f64) {
+        let mut data = self.data.borrow_mut();
+        data.heading_offset = heading - data.raw_heading;
+    }
+}
+
+/// Distance each side's wheels have rolled (inches), averaged over the motors
+/// on that side that can be read, or `None` if none of them can
+pub fn side_travel<M: MotorDevice>(motors: &DriveMotors<M>) -> [Option<f64>; 2] {
+    motors.each_ref().map(|side| {
+        let revolutions = average(side.borrow().each_ref().map(|motor| motor.position().ok()))?;
+        Some(revolutions * GEARING * WHEEL_DIAMETER * PI)
+    })
+}
+
+/// Average of the values that are there, or `None` if none are
+fn average<const N: usize>(values: [Option<f64>; N]) -> Option<f64> {
+    let values = values.iter().flatten();
+    let count = values.clone().count();
+    (count > 0).then(|| values.sum::<f64>() / count as f64)
 }
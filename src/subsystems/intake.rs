@@ -1,31 +1,16 @@
-use alloc::rc::Rc;
+use alloc::{collections::VecDeque, rc::Rc};
 use core::{cell::RefCell, time::Duration};
 
-use vexide::{io::println, prelude::{sleep, spawn, BrakeMode, Motor, OpticalSensor, Task}};
-
-use crate::{RobotSettings, Color};
-
-#[derive(Copy, Clone, Debug)]
-pub enum IntakeCommand {
-    Off,
-    On,
-    Voltage(f64),
-}
-
-impl core::ops::Not for IntakeCommand {
-    type Output = Self;
-
-    fn not(self) -> Self::Output {
-        match self {
-            Self::Off => Self::On,
-            Self::On => Self::Off,
-            Self::Voltage(_) => Self::Off,
-        }
-    }
-}
+use super::command::IntakeCommand;
+use crate::{
+    config::{Color, RobotSettings},
+    hardware::{BrakeMode, MotorDevice, OpticalDevice},
+    platform::{Task, sleep, spawn},
+};
 
 pub struct Intake {
     color_sort: Rc<RefCell<bool>>,
+    ring: Rc<RefCell<Option<Color>>>,
     command: Rc<RefCell<IntakeCommand>>,
     _task: Task<()>,
 }
@@ -33,24 +18,37 @@ pub struct Intake {
 impl Intake {
     // make first motor the primary for now until a solution is made
     pub fn new<const COUNT: usize>(
-        mut motors: [Motor; COUNT],
-        optical_sensor: OpticalSensor,
+        mut motors: [impl MotorDevice + 'static; COUNT],
+        optical_sensor: impl OpticalDevice + 'static,
         settings: Rc<RefCell<RobotSettings>>,
         sprocket_teeth: f64,
         sorting_distance: f64,
     ) -> Self {
         let color_sort = Rc::new(RefCell::new(true));
         let command = Rc::new(RefCell::new(IntakeCommand::Off));
+        let ring = Rc::new(RefCell::new(None));
 
         Self {
             color_sort: color_sort.clone(),
+            ring: ring.clone(),
             command: command.clone(),
             _task: spawn(async move {
                 let sorting_revolutions = sorting_distance / sprocket_teeth;
 
+                // Motor positions at which each opponent ring on the conveyor reaches the
+                // top, oldest first. Rings can be closer together than the sorting
+                // distance, so more than one can be on its way up at once.
+                let mut ejections = VecDeque::new();
+                let mut last_detected = None;
+
                 loop {
-                    println!("{:?}", *command.borrow());
-                    match *command.borrow() {
+                    // Keep track of the ring in front of the sensor so autonomous can check it
+                    let detected = detect_ring(&optical_sensor);
+                    *ring.borrow_mut() = detected;
+
+                    // Copied out so new commands aren't locked out while a ring is sorted
+                    let current = *command.borrow();
+                    match current {
                         IntakeCommand::Voltage(voltage) => {
                             for motor in motors.iter_mut() {
                                 _ = motor.set_voltage(voltage);
@@ -61,36 +59,24 @@ impl Intake {
                                 _ = motor.set_voltage(motor.max_voltage());
                             }
 
+                            let position = motors[0].position().unwrap_or_default();
                             if *color_sort.borrow() {
-                                // Blue ring has a hue of 120 to 240 and red ring has a hue of 0 to 60
-                                // so the oppisite hue is what we are checking for
-                                let color_range = match settings.borrow().curr_color {
-                                    Color::Red => 120.0..240.0,
-                                    Color::Blue => 0.0..60.0,
-                                };
-
-                                let color_hue = optical_sensor.hue().unwrap_or_default();
-                                let proximity = optical_sensor.proximity().unwrap_or_default();
+                                let opponent = settings.borrow().curr_color.opposite();
 
                                 // The oppisite color has obstructed the view, kick the ring out
-                                if color_range.contains(&color_hue) && proximity == 1.0 {
-                                    _ = motors[0].reset_position();
-
-                                    while let Ok(position) = motors[0].position() {
-                                        sleep(Duration::from_millis(20)).await;
-
-                                        // Check if the motor has moved 8.0 inches since its found
-                                        // the wrong ring
-                                        if position.as_revolutions() > sorting_revolutions {
-                                            break;
-                                        }
-                                    }
-
-                                    for motor in motors.iter_mut() {
-                                        _ = motor.brake(BrakeMode::Brake);
-                                    }
-                                    sleep(Duration::from_millis(250)).await;
+                                // once it's moved 8.0 inches up to the top. Only counted as it
+                                // comes into view, since it stays there for a few checks.
+                                if detected == Some(opponent) && last_detected != detected {
+                                    ejections.push_back(position + sorting_revolutions);
+                                }
+                            }
+
+                            if ejections.front().is_some_and(|&top| position > top) {
+                                ejections.pop_front();
+                                for motor in motors.iter_mut() {
+                                    _ = motor.brake(BrakeMode::Brake);
                                 }
+                                sleep(Duration::from_millis(250)).await;
                             }
                         }
                         IntakeCommand::Off => {
@@ -100,6 +86,15 @@ impl Intake {
                         }
                     }
 
+                    if matches!(current, IntakeCommand::On) {
+                        last_detected = detected;
+                    } else {
+                        // Stopped or driven by hand, the rings on the conveyor can end up
+                        // anywhere, so they're forgotten. One still in front of the sensor
+                        // is counted again once the intake is back on.
+                        ejections.clear();
+                        last_detected = None;
+                    }
                     sleep(Duration::from_millis(20)).await;
                 }
             }),
@@ -116,4 +111,127 @@ impl Intake {
         let mut color_sort = self.color_sort.borrow_mut();
         *color_sort = !*color_sort;
     }
+
+    /// The color of the ring currently in front of the optical sensor, if any
+    pub fn ring(&self) -> Option<Color> {
+        *self.ring.borrow()
+    }
+}
+
+/// Finds the color of the ring in front of the optical sensor
+fn detect_ring(optical_sensor: &impl OpticalDevice) -> Option<Color> {
+    // Only count rings that are right up against the sensor
+    let proximity = optical_sensor.proximity().unwrap_or_default();
+    if proximity != 1.0 {
+        return None;
+    }
+
+    // Red rings have a hue of 0 to 60 and blue rings have a hue of 120 to 240
+    let hue = optical_sensor.hue().unwrap_or_default();
+    if (0.0..60.0).contains(&hue) {
+        Some(Color::Red)
+    } else if (120.0..240.0).contains(&hue) {
+        Some(Color::Blue)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{
+        config::MAX_VOLTAGE,
+        hardware::mock::{MockMotor, MockOptical},
+        platform::run,
+    };
+
+    /// How fast the mock intake motor spins at full voltage (revolutions/second)
+    const FREE_SPEED: f64 = 10.0;
+
+    /// Revolutions a ring takes to get from the sensor to the top
+    const SORTING_REVOLUTIONS: f64 = 14.0 / 6.0;
+
+    /// An intake sorting for `alliance`, with a ring of `hue` in front of its
+    /// sensor, along with its mock motor and sensor
+    fn intake(alliance: Color, hue: f64) -> (Intake, MockMotor, MockOptical) {
+        let motor = MockMotor::default();
+        let optical = MockOptical::default();
+        let settings = Rc::new(RefCell::new(RobotSettings {
+            auton_path: Vec::new(),
+            test_auton: false,
+            curr_color: alliance,
+        }));
+        let intake = Intake::new([motor.clone()], optical.clone(), settings, 6.0, 14.0);
+
+        let mut state = optical.state();
+        state.hue = hue;
+        state.proximity = 1.0;
+        drop(state);
+
+        (intake, motor, optical)
+    }
+
+    /// Runs the intake for `duration`, spinning the motor at its voltage.
+    /// Returns where the motor was when it first braked to throw a ring off,
+    /// if it did.
+    fn spin(motor: &MockMotor, optical: &MockOptical, duration: Duration) -> Option<f64> {
+        let mut braked_at = None;
+        run(sleep(duration), duration * 2, |dt| {
+            let mut motor = motor.state();
+            if motor.brake == Some(BrakeMode::Brake) {
+                braked_at.get_or_insert(motor.position);
+            }
+            motor.position += motor.voltage / motor.max_voltage * FREE_SPEED * dt.as_secs_f64();
+
+            // The ring moves out of view once it's on its way up
+            if motor.position > 0.5 {
+                optical.state().proximity = 0.0;
+            }
+        });
+        braked_at
+    }
+
+    /// Runs a ring of `hue` up the intake until the motor has carried it past
+    /// the top
+    fn feed(alliance: Color, hue: f64) -> Option<f64> {
+        let (mut intake, motor, optical) = intake(alliance, hue);
+        intake.set_command(IntakeCommand::On);
+        spin(&motor, &optical, Duration::from_secs(1))
+    }
+
+    #[test]
+    fn ejects_opponent_rings_at_the_top() {
+        let braked_at = feed(Color::Red, 210.0).expect("the blue ring should be thrown off");
+        // Checked every 20 ms, so it can run a little past the top before stopping
+        assert!(
+            (SORTING_REVOLUTIONS..SORTING_REVOLUTIONS + 0.25).contains(&braked_at),
+            "braked at {braked_at} revolutions"
+        );
+    }
+
+    #[test]
+    fn keeps_alliance_rings() {
+        assert_eq!(feed(Color::Red, 10.0), None);
+        assert_eq!(feed(Color::Blue, 210.0), None);
+    }
+
+    #[test]
+    fn forgets_rings_spat_back_out() {
+        let (mut intake, motor, optical) = intake(Color::Red, 210.0);
+
+        // The blue ring starts up the conveyor, then gets pushed back out the bottom
+        intake.set_command(IntakeCommand::On);
+        assert_eq!(spin(&motor, &optical, Duration::from_millis(100)), None);
+        intake.set_command(IntakeCommand::Voltage(-MAX_VOLTAGE));
+        assert_eq!(spin(&motor, &optical, Duration::from_millis(100)), None);
+        intake.set_command(IntakeCommand::Off);
+        assert_eq!(spin(&motor, &optical, Duration::from_millis(100)), None);
+
+        // Running again with nothing on the conveyor shouldn't stop for it
+        intake.set_command(IntakeCommand::On);
+        assert_eq!(spin(&motor, &optical, Duration::from_secs(1)), None);
+    }
 }
@@ -1,18 +1,16 @@
-use core::{cell::RefCell, iter::Cycle, ops::Range, time::Duration};
-
 use alloc::rc::Rc;
-use vexide::prelude::{sleep, spawn, BrakeMode, Motor, RotationSensor, Task};
+use core::{cell::RefCell, iter::Cycle, ops::Range, time::Duration};
 
-#[derive(Clone, Copy, Debug)]
-pub enum LadyBrownCommand {
-    Next,
-    Angle(f64),
-    Voltage(f64),
-}
+use super::command::LadyBrownCommand;
+use crate::{
+    hardware::{BrakeMode, MotorDevice, RotationDevice},
+    platform::{Task, sleep, spawn},
+};
 
 pub struct LadyBrown {
     command: Rc<RefCell<LadyBrownCommand>>,
     stage: Rc<RefCell<usize>>,
+    angle: Rc<RefCell<f64>>,
     stages: Cycle<Range<usize>>,
     _task: Task<()>,
 }
@@ -20,8 +18,8 @@ pub struct LadyBrown {
 impl LadyBrown {
     /// Predefined angles for different stages of the scoring mechanism
     const ANGLES: [f64; 4] = [
-        80.0, // Flick tolerance (default resting position)
-        8.0, // Intake (aligns with hooks to pick up rings)
+        80.0,  // Flick tolerance (default resting position)
+        8.0,   // Intake (aligns with hooks to pick up rings)
         100.0, // Align (positioning before scoring)
         150.0, // Scoring (final scoring position on wall stake)
     ];
@@ -31,12 +29,13 @@ impl LadyBrown {
 
     /// Initializes the scoring mechanism with motors and optional sensors
     pub fn new<const COUNT: usize>(
-        mut motors: [Motor; COUNT],
-        mut rotation_sensor: RotationSensor,
+        mut motors: [impl MotorDevice + 'static; COUNT],
+        mut rotation_sensor: impl RotationDevice + 'static,
         gear_ratio: Option<f64>,
     ) -> Self {
         let command = Rc::new(RefCell::new(LadyBrownCommand::Angle(0.0)));
         let stage = Rc::new(RefCell::new(0));
+        let angle = Rc::new(RefCell::new(0.0));
         let mut stages = (0..Self::ANGLES.len()).cycle();
         _ = stages.next();
         _ = rotation_sensor.reset_position();
@@ -44,15 +43,19 @@ impl LadyBrown {
         Self {
             command: command.clone(),
             stage: stage.clone(),
+            angle: angle.clone(),
             stages, // Infinite cycle through the stages
             _task: spawn(async move {
                 loop {
                     // Run the loop every 20ms to prevent CPU overload
                     sleep(Duration::from_millis(20)).await;
 
-                    let Some(angle) = get_angle(&rotation_sensor, &motors, gear_ratio) else {
+                    let Some(current_angle) = get_angle(&rotation_sensor, &motors, gear_ratio)
+                    else {
                         continue;
                     };
+                    *angle.borrow_mut() = current_angle;
+                    let angle = current_angle;
 
                     // Convert the angle to degrees, applying the gear ratio if provided
                     // as of right now rotation sensor should not work because it keeps the
@@ -94,7 +97,7 @@ impl LadyBrown {
                         }
                         // Cycle should never go past 3 because of the infinite cycle
                         _ => unreachable!(),
-                    } 
+                    }
                 }
             }),
         }
@@ -106,6 +109,11 @@ impl LadyBrown {
         }
     }
 
+    /// The last angle the mechanism was measured at (degrees)
+    pub fn angle(&self) -> f64 {
+        *self.angle.borrow()
+    }
+
     /// Advances to the next stage in the cycle
     pub fn next(&mut self) {
         let mut stage = self.stage.borrow_mut();
@@ -113,13 +121,85 @@ impl LadyBrown {
     }
 }
 
-fn get_angle(rotation_sensor: &RotationSensor, motors: &[Motor], gear_ratio: Option<f64>) -> Option<f64> {
+fn get_angle(
+    rotation_sensor: &impl RotationDevice,
+    motors: &[impl MotorDevice],
+    gear_ratio: Option<f64>,
+) -> Option<f64> {
     // Try to get the angle from the rotation sensor first
-    
-    if let Ok(angle) = rotation_sensor.position() {
-        return Some(angle.as_degrees() * gear_ratio.unwrap_or(1.0));
+
+    if let Ok(revolutions) = rotation_sensor.position() {
+        return Some(revolutions * 360.0 * gear_ratio.unwrap_or(1.0));
     }
 
     // If the sensor is unavailable or failed, try the first motor
-    Some(motors.first()?.position().ok()?.as_degrees() * gear_ratio.unwrap_or(1.0))
+    Some(motors.first()?.position().ok()? * 360.0 * gear_ratio.unwrap_or(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hardware::mock::{MockMotor, MockRotation},
+        platform::run,
+    };
+
+    /// How fast the mock arm turns at full voltage (degrees/second)
+    const FREE_SPEED: f64 = 600.0;
+
+    /// Gives the lady brown a second to move, turning the arm with its motor
+    fn settle(motor: &MockMotor, rotation: &MockRotation) {
+        run(
+            sleep(Duration::from_secs(1)),
+            Duration::from_secs(2),
+            |dt| {
+                let motor = motor.state();
+                if motor.brake.is_none() {
+                    let degrees = motor.voltage / motor.max_voltage * FREE_SPEED * dt.as_secs_f64();
+                    rotation.state().position += degrees / 360.0;
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn moves_up_to_each_stage_and_holds() {
+        let motor = MockMotor::default();
+        let rotation = MockRotation::default();
+        let mut lady_brown = LadyBrown::new([motor.clone()], rotation.clone(), None);
+
+        for angle in [8.0, 100.0, 150.0] {
+            lady_brown.next();
+            settle(&motor, &rotation);
+
+            // Checked every 20 ms, so it can run a little past before holding
+            let reached = lady_brown.angle();
+            assert!(
+                (angle..angle + 10.0).contains(&reached),
+                "stopped at {reached}° instead of {angle}°"
+            );
+            assert_eq!(motor.state().brake, Some(BrakeMode::Hold));
+        }
+    }
+
+    #[test]
+    fn falls_back_to_rest() {
+        let motor = MockMotor::default();
+        let rotation = MockRotation::default();
+        let mut lady_brown = LadyBrown::new([motor.clone()], rotation.clone(), None);
+
+        // Around from scoring to the resting position
+        for _ in 0..4 {
+            lady_brown.next();
+        }
+        rotation.state().position = 150.0 / 360.0;
+        settle(&motor, &rotation);
+
+        assert!(
+            lady_brown.angle() <= 80.0,
+            "stopped at {}°",
+            lady_brown.angle()
+        );
+        assert_eq!(motor.state().brake, Some(BrakeMode::Coast));
+    }
 }
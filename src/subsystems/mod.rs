@@ -1,3 +1,4 @@
+pub mod command;
 pub mod drivetrain;
 pub mod intake;
 pub mod lady_brown;